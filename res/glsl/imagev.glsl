@@ -1,17 +1,15 @@
 #version 330 core
 layout (location = 0) in vec3 in_pos;
 layout (location = 1) in vec2 in_uv;
+layout (location = 2) in vec4 in_color;
 
 out vec2 uv;
 out vec4 c_mod;
 
-uniform mat4 mvp;
-uniform vec4 uv_rect;
-uniform vec4 color;
-
 void main()
 {
-   gl_Position = mvp * vec4(in_pos.xyz, 1.0);
-   uv = in_uv * uv_rect.zw + uv_rect.xy;
-   c_mod = color;
-}
\ No newline at end of file
+   // Batched quads are transformed on the CPU
+   gl_Position = vec4(in_pos.xyz, 1.0);
+   uv = in_uv;
+   c_mod = in_color;
+}
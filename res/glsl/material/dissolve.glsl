@@ -0,0 +1,34 @@
+#version 330 core
+
+in vec2 uv;
+in vec4 c_mod;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+// 0 shows the whole image, 1 none of it
+uniform float threshold;
+uniform float noise_scale;
+uniform float edge_width;
+uniform vec4 edge_color;
+
+float hash(vec2 p)
+{
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+void main()
+{
+    float noise = hash(floor(uv * noise_scale));
+    if (noise < threshold) {
+        discard;
+    }
+
+    vec4 color = texture(tex1, uv) * c_mod;
+    if (noise < threshold + edge_width) {
+        color.rgb = edge_color.rgb;
+        color.a *= edge_color.a;
+    }
+    FragColor = color;
+}
@@ -0,0 +1,27 @@
+#version 330 core
+
+in vec2 uv;
+in vec4 c_mod;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+uniform vec4 outline_color;
+
+// Size of a texel of tex1 in uv, times the outline width in texels
+uniform vec2 texel;
+
+void main()
+{
+    vec4 color = texture(tex1, uv) * c_mod;
+
+    float neighbours = max(
+        max(texture(tex1, uv + vec2(texel.x, 0.0)).a, texture(tex1, uv - vec2(texel.x, 0.0)).a),
+        max(texture(tex1, uv + vec2(0.0, texel.y)).a, texture(tex1, uv - vec2(0.0, texel.y)).a));
+
+    if (color.a < 0.5 && neighbours >= 0.5) {
+        color = vec4(outline_color.rgb, outline_color.a * c_mod.a);
+    }
+    FragColor = color;
+}
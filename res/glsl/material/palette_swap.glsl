@@ -0,0 +1,22 @@
+#version 330 core
+
+in vec2 uv;
+in vec4 c_mod;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+// Colors within tolerance of from are moved to to, keeping their shading
+uniform vec3 from;
+uniform vec3 to;
+uniform float tolerance;
+
+void main()
+{
+    vec4 color = texture(tex1, uv);
+    if (distance(color.rgb, from) < tolerance) {
+        color.rgb = clamp(to + (color.rgb - from), 0.0, 1.0);
+    }
+    FragColor = color * c_mod;
+}
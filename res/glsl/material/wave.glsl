@@ -0,0 +1,20 @@
+#version 330 core
+
+in vec2 uv;
+in vec4 c_mod;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+uniform float time;
+
+// Horizontal offset in uv and waves per image height
+uniform float amplitude;
+uniform float frequency;
+
+void main()
+{
+    vec2 offset = vec2(sin(uv.y * frequency * 6.2831853 + time) * amplitude, 0.0);
+    FragColor = texture(tex1, uv + offset) * c_mod;
+}
@@ -0,0 +1,28 @@
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+uniform vec2 resolution;
+
+// Brightness above which pixels glow, how far and how strongly
+uniform float threshold;
+uniform float radius;
+uniform float intensity;
+
+void main()
+{
+    vec4 color = texture(tex1, uv);
+    vec2 texel = radius / resolution;
+    vec3 glow = vec3(0.0);
+    for (int x = -3; x <= 3; x++) {
+        for (int y = -3; y <= 3; y++) {
+            vec3 tap = texture(tex1, uv + vec2(x, y) * texel).rgb;
+            float brightness = dot(tap, vec3(0.2126, 0.7152, 0.0722));
+            glow += tap * max(brightness - threshold, 0.0);
+        }
+    }
+    FragColor = vec4(color.rgb + glow / 49.0 * intensity, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+uniform vec2 resolution;
+
+// Distance in pixels between samples
+uniform float radius;
+
+void main()
+{
+    vec2 texel = radius / resolution;
+    vec3 sum = vec3(0.0);
+    for (int x = -2; x <= 2; x++) {
+        for (int y = -2; y <= 2; y++) {
+            sum += texture(tex1, uv + vec2(x, y) * texel).rgb;
+        }
+    }
+    FragColor = vec4(sum / 25.0, 1.0);
+}
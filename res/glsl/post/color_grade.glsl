@@ -0,0 +1,21 @@
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+// Multiplied into the color, 0 saturation is grayscale and 1 contrast leaves the frame as is
+uniform vec3 tint;
+uniform float saturation;
+uniform float contrast;
+
+void main()
+{
+    vec3 color = texture(tex1, uv).rgb;
+    float gray = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(gray), color, saturation);
+    color = (color - 0.5) * contrast + 0.5;
+    FragColor = vec4(color * tint, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+uniform vec2 resolution;
+uniform float time;
+
+// Darkness of the scanlines and how much the picture flickers
+uniform float intensity;
+uniform float flicker;
+
+void main()
+{
+    vec4 color = texture(tex1, uv);
+    float line = sin(uv.y * resolution.y * 3.14159) * 0.5 + 0.5;
+    float shade = mix(1.0, line, intensity) * (1.0 - flicker * (sin(time * 60.0) * 0.5 + 0.5));
+    FragColor = vec4(color.rgb * shade, 1.0);
+}
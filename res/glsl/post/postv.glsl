@@ -0,0 +1,15 @@
+#version 330 core
+layout (location = 0) in vec3 in_pos;
+
+out vec2 uv;
+
+uniform float flip_y;
+
+void main()
+{
+   gl_Position = vec4(in_pos.xy * 2.0, 0.0, 1.0);
+   uv = in_pos.xy + 0.5;
+   if (flip_y > 0.5) {
+      uv.y = 1.0 - uv.y;
+   }
+}
@@ -0,0 +1,19 @@
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex1;
+
+// Distance from the center where darkening starts and how dark the corners get
+uniform float radius;
+uniform float strength;
+
+void main()
+{
+    vec4 color = texture(tex1, uv);
+    float dist = distance(uv, vec2(0.5));
+    float shade = smoothstep(radius, radius + 0.5, dist) * strength;
+    FragColor = vec4(color.rgb * (1.0 - shade), 1.0);
+}
@@ -1,25 +1,59 @@
 use sfml::system::Vector2;
-use sfml::window::Window;
+use sfml::window::{Event as WindowEvent, Key, Window};
+use std::any::Any;
 
+use crate::core::component::focusable::Focusable;
 use crate::core::component::pre_frame::PreFrame;
-use crate::core::entity::Entity;
+use crate::core::drag::Drag;
+use crate::core::entity::commands::EntityCommands;
+use crate::core::entity::pick::{pick, PointerHit};
+use crate::core::entity::{Entity, EntityId, EntityIndex, EntityLookupError};
 use crate::core::event::Event::{self, ImageLoadEvent, SFMLEvent};
-use crate::core::event::ImageLoadEventPayload;
+use crate::core::event::{CustomEvent, ImageLoadEventPayload, TextInputPayload};
+use crate::core::event_queue::{EventPriority, EventQueue, EventTarget};
+use crate::core::focus::{focus_chain, is_reachable, text_input, Focus};
+use crate::core::input::action::ActionMap;
+use crate::core::input::gamepad::Gamepads;
+use crate::core::input::recording::{tree_checksum, Recorder, Replayer};
+use crate::core::input::state::InputState;
+use crate::core::pointer::{pointer_position, Pointer};
 use crate::core::renderer::window::{make_window, WindowConfig, WindowStyle};
 use crate::core::renderer::Renderer;
 use crate::core::resource::Resources;
+use crate::core::scene::BehaviorRegistry;
+use crate::core::scheduler::Scheduler;
 use crate::game::scene::entry::make_entry;
 use crate::game::state::GameState;
+use crate::util::random::SessionRng;
 use crate::util::timer::Timer;
 
+extern crate nalgebra_glm as glm;
+
 #[derive(Default)]
 pub struct App {
     window: Option<Window>,
     window_config: WindowConfig,
-    app_events: Vec<Event>,
+    events: EventQueue,
+    entity_index: EntityIndex,
+    focus: Focus,
+    injected_events: Vec<WindowEvent>,
+    recorder: Option<Recorder>,
+    replayer: Option<Replayer>,
+    replay_diverged: bool,
+    pub actions: ActionMap,
+    pub gamepads: Gamepads,
+    pub scheduler: Scheduler,
+    pub input: InputState,
+    pub pointer: Pointer,
+    pub drag: Drag,
     pub state: GameState,
+    pub commands: EntityCommands,
+    pub behaviors: BehaviorRegistry,
     pub resource: Resources,
     pub renderer: Renderer,
+
+    /// Use instead of `rand::thread_rng` so recorded sessions replay the same numbers
+    pub rng: SessionRng,
 }
 
 impl App {
@@ -46,7 +80,12 @@ impl App {
             self.renderer
                 .update_size(config.width as f32, config.height as f32);
 
-            self.app_events.push(Event::WindowUpdated(config.clone()));
+            // Layout changes should be seen before any other queued events
+            self.post_event_with_priority(
+                EventTarget::Broadcast,
+                Event::WindowUpdated(config.clone()),
+                EventPriority::High,
+            );
         }
     }
 
@@ -54,16 +93,315 @@ impl App {
         self.window_config.clone()
     }
 
-    fn handle_events(&mut self, root: &mut Entity) {
-        // Handle any queued up application events first
+    /// Resolves a slash separated path from the root Entity, eg: "entry/testbed/test_button".
+    /// The index is refreshed at the start of a frame after Entities were added or removed.
+    pub fn find_entity_id(&self, path: &str) -> Result<EntityId, EntityLookupError> {
+        self.entity_index.id_of(path)
+    }
+
+    pub fn find_entity_path(&self, id: EntityId) -> Result<&str, EntityLookupError> {
+        self.entity_index.path_of(id)
+    }
+
+    /// Queues an event for the whole tree. It is delivered exactly once on the next event pump.
+    /// Takes `&self` so it can be called from `update` as well as event handlers.
+    pub fn post_event(&self, event: Event) {
+        self.events
+            .post(EventTarget::Broadcast, event, EventPriority::Normal);
+    }
+
+    /// Queues an event for the subtree of the Entity at a slash separated path from the root
+    pub fn post_event_to(&self, path: &str, event: Event) {
+        self.events.post(
+            EventTarget::Path(path.to_string()),
+            event,
+            EventPriority::Normal,
+        );
+    }
+
+    pub fn post_event_with_priority(
+        &self,
+        target: EventTarget,
+        event: Event,
+        priority: EventPriority,
+    ) {
+        self.events.post(target, event, priority);
+    }
+
+    /// Queues a game defined event for the whole tree, see `post_event`
+    pub fn post_custom_event<T: Any>(&self, payload: T) {
+        self.post_event(Event::Custom(CustomEvent::new(payload)));
+    }
+
+    /// Queues a synthetic window event, eg: a joystick event from a simulated controller. Injected
+    /// events are handled before the real window events of the next frame. They are recorded like
+    /// real ones, so while replaying they are ignored in favour of the recorded copies.
+    pub fn inject_window_event(&mut self, event: WindowEvent) {
+        if self.replayer.is_none() {
+            self.injected_events.push(event);
+        }
+    }
+
+    /// Entity which owns keyboard input, see core::focus
+    pub fn focused(&self) -> Option<EntityId> {
+        self.focus.focused()
+    }
+
+    /// Moves keyboard focus, None clears it. FocusLost and FocusGained are queued for the
+    /// Entities involved.
+    pub fn set_focus(&self, id: Option<EntityId>) {
+        let previous = self.focus.replace(id);
+        if previous == id {
+            return;
+        }
+
+        if let Some(previous) = previous {
+            self.post_event_with_priority(
+                EventTarget::Entity(previous),
+                Event::FocusLost(previous),
+                EventPriority::High,
+            );
+        }
+        if let Some(id) = id {
+            self.post_event_with_priority(
+                EventTarget::Entity(id),
+                Event::FocusGained(id),
+                EventPriority::High,
+            );
+        }
+    }
+
+    /// Moves focus along the Tab chain. Returns false when nothing in the tree is Focusable.
+    fn cycle_focus(&self, root: &mut Entity, backwards: bool) -> bool {
+        let chain = focus_chain(root);
+        match self.focus.next_in_chain(&chain, backwards) {
+            Some(id) => {
+                self.set_focus(Some(id));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pressing a Focusable Entity focuses it, pressing anywhere else clears focus
+    fn focus_on_press(&self, root: &mut Entity, hit: Option<PointerHit>) {
+        let focusable =
+            hit.filter(|h| matches!(root.find_by_id(h.id), Ok(e) if e.has::<Focusable>()));
+        self.set_focus(focusable.map(|h| h.id));
+    }
+
+    /// Delivers a key press or text event, and the TextInput derived from it, to the subtree of
+    /// the focused Entity. Returns whether a handler consumed either of them. Focus is cleared
+    /// instead when the focused Entity is gone, hidden or inactive.
+    fn handle_focused_event(
+        &mut self,
+        root: &mut Entity,
+        id: EntityId,
+        event: WindowEvent,
+    ) -> bool {
+        if !is_reachable(root, id) {
+            // The focused Entity can not take input anymore, input goes back to the whole tree
+            match root.find_by_id(id) {
+                Ok(_) => self.set_focus(None),
+                Err(_) => {
+                    self.focus.replace(None);
+                }
+            }
+            return false;
+        }
+
+        let mut events = vec![SFMLEvent(event)];
+        if let Some(input) = text_input(&event) {
+            events.push(Event::TextInput(TextInputPayload { entity: id, input }));
+        }
+
+        let mut consumed = false;
+        for e in events {
+            // The Entity may have been removed by an earlier handler
+            if let Ok(target) = root.find_by_id(id) {
+                consumed |= target.handle_event(&mut Some(self), &e);
+            }
+        }
+        consumed
+    }
+
+    /// Records the window events and deltatime of every frame, `rng` is reseeded and the seed
+    /// recorded. The recording is written to `path`, along with a checksum of the tree, when the
+    /// window closes.
+    pub fn record_to(&mut self, path: &str) {
+        self.replayer = None;
+        self.rng = SessionRng::default();
+        self.recorder = Some(Recorder::new(path, self.rng.seed()));
+    }
+
+    /// Replays a recording instead of live input. Every frame injects the recorded events and uses
+    /// the recorded deltatime, `rng` starts from the recorded seed. After the last frame the tree
+    /// checksum is compared with the recorded one and the window closes.
+    pub fn replay_from(&mut self, path: &str) -> Result<(), String> {
+        let replayer = Replayer::load(path)?;
+        self.recorder = None;
+        self.rng = SessionRng::new(replayer.seed());
+        self.replayer = Some(replayer);
+        Ok(())
+    }
+
+    /// Whether the last replay ended with a different tree than the recording
+    pub fn replay_diverged(&self) -> bool {
+        self.replay_diverged
+    }
+
+    /// Deltatime of the new frame given the measured one, taken from the recording while replaying
+    fn begin_frame(&mut self, dt: f32) -> f32 {
+        if let Some(frame) = self.replayer.as_mut().and_then(|r| r.next_frame()) {
+            self.injected_events
+                .extend(frame.events.iter().map(|e| e.to_window_event()));
+            return frame.dt;
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.begin_frame(dt);
+        }
+        dt
+    }
+
+    /// Closes the window once the last recorded frame ran and reports whether the tree matches
+    fn check_replay(&mut self, root: &mut Entity) {
+        if !matches!(&self.replayer, Some(replayer) if replayer.is_finished()) {
+            return;
+        }
+
+        let replayer = self.replayer.take().unwrap();
+        let checksum = tree_checksum(&self.resource, root);
+        match replayer.expected_checksum() {
+            Some(expected) if expected == checksum => {
+                println!("Replay matched, checksum {:016x}", checksum)
+            }
+            Some(expected) => {
+                println!(
+                    "Replay diverged, expected checksum {:016x} but got {:016x}",
+                    expected, checksum
+                );
+                self.replay_diverged = true;
+            }
+            None => println!(
+                "Replay finished without a recorded checksum, got {:016x}",
+                checksum
+            ),
+        }
+
+        self.close_window();
+    }
+
+    /// Delivers a window event followed by the gamepad, action, pointer and drag events derived
+    /// from it. Key presses and text go to the focused Entity first, when there is one.
+    fn handle_window_event(&mut self, root: &mut Entity, event: WindowEvent) {
+        // Hit test before handlers run so the pointer events match what was on screen
+        let hit = pointer_position(&event).and_then(|point| pick(root, &self.resource, point));
+        if let (WindowEvent::MouseButtonReleased { button, .. }, Some(_)) =
+            (event, self.drag.dragging())
+        {
+            // A drag ends with a drop, not a click
+            self.pointer.cancel_click(button);
+        }
+        let mut pointer_events = self.pointer.handle_event(&event, hit);
+        pointer_events.extend(self.drag.handle_event(root, &self.resource, &event, hit));
+
+        if let WindowEvent::MouseButtonPressed { .. } = event {
+            self.focus_on_press(root, hit);
+        }
+
+        // The snapshot is updated first so handlers see state consistent with the event
+        self.input.handle_event(&SFMLEvent(event));
+
+        if let WindowEvent::KeyPressed {
+            code: Key::Tab,
+            shift,
+            ..
+        } = event
+        {
+            // Tab moves focus along the chain instead of reaching the tree
+            if self.cycle_focus(root, shift) {
+                return;
+            }
+        }
+
+        // The focused Entity sees key presses and text first. The ones it does not consume reach
+        // the whole tree and are translated to actions like any other event.
+        if let (WindowEvent::KeyPressed { .. } | WindowEvent::TextEntered { .. }, Some(id)) =
+            (event, self.focused())
         {
-            let mut app_events = Vec::new();
-            for e in &self.app_events {
-                app_events.push(e.clone());
+            if self.handle_focused_event(root, id, event) {
+                return;
             }
+        }
+
+        let gamepad_events = self.gamepads.handle_event(&event);
+
+        // Pointer events go first, an Entity which consumes one keeps the window event it came
+        // from away from the rest of the tree, eg: a button pressed over another Entity
+        let mut consumed = false;
+        for (id, pointer_event) in pointer_events {
+            // The Entity may have been removed by an earlier handler
+            if let Ok(target) = root.find_by_id(id) {
+                consumed |= target.handle_event(&mut Some(self), &pointer_event);
+            }
+        }
+
+        if !consumed {
+            self.deliver_to_tree(root, &SFMLEvent(event));
+        }
+
+        for e in gamepad_events {
+            self.input.handle_event(&e);
+            self.deliver_to_tree(root, &e);
+        }
+    }
+
+    /// Broadcasts an event followed by the actions it triggers
+    fn deliver_to_tree(&mut self, root: &mut Entity, e: &Event) {
+        root.handle_event(&mut Some(self), e);
+
+        for action_event in self.actions.translate(e) {
+            root.handle_event(&mut Some(self), &action_event);
+        }
+    }
+
+    /// Queues the timers which fire this frame. Timers of Entities missing from the tree are cancelled.
+    fn fire_timers(&mut self, dt: f32) {
+        let index = &self.entity_index;
+        for timer in self.scheduler.advance(dt, |id| index.path_of(id).is_ok()) {
+            self.events.post(
+                EventTarget::Entity(timer.entity),
+                Event::Timer(timer),
+                EventPriority::Normal,
+            );
+        }
+    }
 
-            for e in app_events {
-                root.handle_event(&mut Some(self), &e);
+    /// Pumps every event source once per frame, in order: queued app events (by priority, then
+    /// posting order), async load events, injected window events and finally SFML window events.
+    fn handle_events(&mut self, root: &mut Entity) {
+        // Handle queued application events first. Events posted while these are delivered wait for
+        // the next frame.
+        {
+            for queued in self.events.drain() {
+                match &queued.target {
+                    EventTarget::Broadcast => {
+                        root.handle_event(&mut Some(self), &queued.event);
+                    }
+                    EventTarget::Path(path) => match root.find_by_path(path) {
+                        Ok(target) => {
+                            target.handle_event(&mut Some(self), &queued.event);
+                        }
+                        Err(err) => println!("Dropping event {:?}: {:?}", queued.event, err),
+                    },
+                    EventTarget::Entity(id) => match root.find_by_id(*id) {
+                        Ok(target) => {
+                            target.handle_event(&mut Some(self), &queued.event);
+                        }
+                        Err(err) => println!("Dropping event {:?}: {:?}", queued.event, err),
+                    },
+                }
             }
         }
 
@@ -81,15 +419,31 @@ impl App {
             }
 
             for e in image_load_events.iter() {
-                root.handle_event(&mut Some(self), e)
+                root.handle_event(&mut Some(self), e);
             }
         }
 
-        // Lastly, handle SFML window events
+        // Lastly, handle injected and SFML window events
         {
+            for event in std::mem::take(&mut self.injected_events) {
+                // Replayed events are already in the recording this replay came from
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(&event);
+                }
+                self.handle_window_event(root, event);
+            }
+
             if self.window.is_some() {
                 while let Some(event) = self.window.as_mut().unwrap().poll_event() {
-                    root.handle_event(&mut Some(self), &SFMLEvent(event));
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(&event);
+                    }
+
+                    // Live input would make a replay diverge, only closing the window is honored
+                    if self.replayer.is_some() && event != WindowEvent::Closed {
+                        continue;
+                    }
+                    self.handle_window_event(root, event);
                 }
             }
         }
@@ -117,15 +471,323 @@ impl App {
             root.add_child(make_entry(self));
 
             while self.window.as_ref().unwrap().is_open() {
-                let dt = frame_timer.dt();
-
-                self.handle_events(&mut root);
-                root.update(self, dt);
-                root.reorder_children();
+                let dt = self.begin_frame(frame_timer.dt());
+                self.update_frame(&mut root, dt);
                 self.window.as_mut().unwrap().set_active(true);
-                root.render_components(self, (0., 0.));
+                self.renderer.begin_frame(dt);
+                root.render_components(self, &glm::identity());
+                self.renderer.end_frame();
                 self.window.as_mut().unwrap().display();
+                self.check_replay(&mut root);
+            }
+
+            self.finish_recording(&mut root);
+        }
+    }
+
+    /// Everything a frame does before rendering: timers, events, updates and deferred commands
+    fn update_frame(&mut self, root: &mut Entity, dt: f32) {
+        if self.entity_index.is_stale() {
+            self.entity_index = EntityIndex::build(root);
+        }
+        self.input.begin_frame();
+        self.fire_timers(dt);
+        self.handle_events(root);
+        root.update(self, dt);
+        self.commands.flush(root);
+        root.reorder_children();
+    }
+
+    /// Saves the recording, if one is running, with the checksum of the tree
+    fn finish_recording(&mut self, root: &mut Entity) {
+        if let Some(recorder) = self.recorder.take() {
+            let checksum = tree_checksum(&self.resource, root);
+            if let Err(err) = recorder.finish(checksum) {
+                println!("Failed to save recording: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entity::behavior::EntityBehavior;
+    use crate::core::event::EventContext;
+    use crate::core::input::action::{ActionBindings, Binding};
+    use crate::core::input::recording::Recording;
+
+    use rand::Rng;
+    use serde_json::{json, Value};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Draws a random number on A and answers it with an injected B, like a simulated controller
+    #[derive(Default)]
+    struct Session {
+        elapsed: f32,
+        rolls: Vec<u32>,
+        answers: u32,
+    }
+
+    impl EntityBehavior for Session {
+        fn update(&mut self, _e: &mut Entity, _app: &App, dt: f32) {
+            self.elapsed += dt;
+        }
+
+        fn event(
+            &mut self,
+            _e: &mut Entity,
+            app: &mut Option<&mut App>,
+            ev: &Event,
+            _ctx: &mut EventContext,
+        ) {
+            if let (Some(a), SFMLEvent(WindowEvent::KeyPressed { code, .. })) = (app, ev) {
+                match *code {
+                    Key::A => {
+                        self.rolls.push(a.rng.gen_range(0..1000));
+                        a.inject_window_event(key_pressed(Key::B));
+                    }
+                    Key::B => self.answers += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        fn checksum_state(&self) -> Option<Value> {
+            Some(json!({
+                "elapsed": self.elapsed,
+                "rolls": self.rolls,
+                "answers": self.answers,
+            }))
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn key_pressed(code: Key) -> WindowEvent {
+        WindowEvent::KeyPressed {
+            code,
+            alt: false,
+            ctrl: false,
+            shift: false,
+            system: false,
+        }
+    }
+
+    fn session_root() -> Entity {
+        let mut root = Entity::new("session");
+        root.set_behavior(Session::default());
+        root
+    }
+
+    fn recording_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("omega-{}-{}.json", name, std::process::id()));
+        path.to_string_lossy().to_string()
+    }
+
+    /// Records a few frames of key presses with uneven deltatimes and returns the checksum
+    fn record_session(path: &str) -> u64 {
+        let mut app = App::default();
+        let mut root = session_root();
+        app.record_to(path);
+
+        for frame in 0..10 {
+            if frame % 3 == 0 {
+                app.inject_window_event(key_pressed(Key::A));
+            }
+            let dt = app.begin_frame(0.01 + frame as f32 * 0.003);
+            app.update_frame(&mut root, dt);
+        }
+
+        let checksum = tree_checksum(&app.resource, &mut root);
+        app.finish_recording(&mut root);
+        checksum
+    }
+
+    /// Replays a recording to the end and returns the checksum and whether the App saw divergence
+    fn replay_session(path: &str) -> (u64, bool) {
+        let mut app = App::default();
+        let mut root = session_root();
+        app.replay_from(path).unwrap();
+
+        let mut checksum = 0;
+        while app.replayer.is_some() {
+            // The measured deltatime is ignored while replaying
+            let dt = app.begin_frame(1.);
+            app.update_frame(&mut root, dt);
+            checksum = tree_checksum(&app.resource, &mut root);
+            app.check_replay(&mut root);
+        }
+        (checksum, app.replay_diverged())
+    }
+
+    #[test]
+    fn replay_matches_recorded_session() {
+        let path = recording_path("replay");
+        let recorded = record_session(&path);
+
+        // Injected answers are recorded too, 4 presses of A and 3 of B. The last answer would be
+        // handled after the session ended.
+        let recording = Recording::load(&path).unwrap();
+        let events: usize = recording.frames.iter().map(|f| f.events.len()).sum();
+        assert_eq!(events, 7);
+        assert_eq!(recording.checksum, Some(recorded));
+
+        let (replayed, diverged) = replay_session(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed, recorded);
+        assert!(!diverged);
+    }
+
+    #[test]
+    fn replay_with_another_seed_diverges() {
+        let path = recording_path("reseeded");
+        record_session(&path);
+
+        let mut recording = Recording::load(&path).unwrap();
+        recording.seed = recording.seed.wrapping_add(1);
+        recording.save(&path).unwrap();
+
+        let (_, diverged) = replay_session(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(diverged);
+    }
+
+    #[test]
+    fn entity_index_follows_spawns_and_despawns() {
+        let mut app = App::default();
+        let mut root = Entity::new("root");
+        app.update_frame(&mut root, 0.);
+        assert!(app.find_entity_id("child").is_err());
+
+        let child = app.commands.spawn(root.id(), Entity::new("child"));
+        app.update_frame(&mut root, 0.);
+        // Commands are applied at the end of the frame, the next one sees the new Entity
+        app.update_frame(&mut root, 0.);
+        assert_eq!(app.find_entity_id("child"), Ok(child));
+        assert_eq!(app.find_entity_path(child), Ok("child"));
+
+        app.commands.despawn(child);
+        app.update_frame(&mut root, 0.);
+        app.update_frame(&mut root, 0.);
+        assert!(app.find_entity_id("child").is_err());
+        assert!(app.find_entity_path(child).is_err());
+    }
+
+    type KeyLog = Rc<RefCell<Vec<String>>>;
+
+    /// Logs the keyboard and action events its Entity sees, consuming them when `consume` is set
+    struct KeyListener {
+        log: KeyLog,
+        consume: bool,
+    }
+
+    impl EntityBehavior for KeyListener {
+        fn event(
+            &mut self,
+            e: &mut Entity,
+            _app: &mut Option<&mut App>,
+            ev: &Event,
+            ctx: &mut EventContext,
+        ) {
+            let seen = match ev {
+                SFMLEvent(WindowEvent::KeyPressed { code, .. }) => format!("{:?}", code),
+                Event::TextInput(p) => format!("{:?}", p.input),
+                Event::ActionPressed(action) => action.clone(),
+                _ => return,
+            };
+            self.log.borrow_mut().push(format!("{} {}", e.name, seen));
+            if self.consume {
+                ctx.stop_propagation();
             }
         }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// App binding Space to jump and a root listening for keys with a focused child, returns its id
+    fn focus_tree(log: &KeyLog, consume: bool) -> (App, Entity, EntityId) {
+        let mut app = App::default();
+        let mut bindings = ActionBindings::default();
+        bindings.bind("jump", Binding::Key { key: Key::Space });
+        app.actions.set_bindings(bindings);
+
+        let focused = Entity::with_behavior(
+            "focused",
+            KeyListener {
+                log: log.clone(),
+                consume,
+            },
+        );
+        let id = focused.id();
+        let mut root = Entity::with_behavior(
+            "root",
+            KeyListener {
+                log: log.clone(),
+                consume: false,
+            },
+        );
+        root.add_child(focused);
+        app.set_focus(Some(id));
+        (app, root, id)
+    }
+
+    #[test]
+    fn focused_entity_consumes_keys_before_the_tree() {
+        let log = KeyLog::default();
+        let (mut app, mut root, _) = focus_tree(&log, true);
+
+        app.handle_window_event(&mut root, key_pressed(Key::Space));
+        app.handle_window_event(&mut root, key_pressed(Key::Backspace));
+        assert_eq!(
+            *log.borrow(),
+            ["focused Space", "focused Backspace", "focused Backspace"]
+        );
+    }
+
+    #[test]
+    fn unconsumed_keys_reach_the_tree_and_actions() {
+        let log = KeyLog::default();
+        let (mut app, mut root, _) = focus_tree(&log, false);
+
+        app.handle_window_event(&mut root, key_pressed(Key::Space));
+        assert_eq!(
+            *log.borrow(),
+            [
+                "focused Space",
+                "focused Space",
+                "root Space",
+                "focused jump",
+                "root jump",
+            ]
+        );
+        assert!(app.actions.is_pressed("jump"));
+    }
+
+    #[test]
+    fn hidden_or_inactive_entities_lose_focus() {
+        // Hidden Entities still see broadcasts, inactive ones do not
+        let hidden = (|e: &mut Entity| e.visible = false) as fn(&mut Entity);
+        let inactive = |e: &mut Entity| e.active = false;
+        for (hide, expected) in [
+            (
+                hidden,
+                &["focused Space", "root Space", "focused jump", "root jump"][..],
+            ),
+            (inactive, &["root Space", "root jump"][..]),
+        ] {
+            let log = KeyLog::default();
+            let (mut app, mut root, id) = focus_tree(&log, false);
+            hide(root.find_by_id(id).unwrap());
+
+            app.handle_window_event(&mut root, key_pressed(Key::Space));
+            assert_eq!(app.focused(), None);
+            assert_eq!(*log.borrow(), expected);
+        }
     }
 }
@@ -1,23 +1,37 @@
 use crate::core::audio::sound::Sound;
 use crate::core::component::Component;
+use crate::core::entity::Entity;
+
+use sfml::audio::SoundStatus;
 
 use core::any::Any;
 
 pub struct AudioClip {
     pub name: String,
+    pub path: String,
     pub sound: Sound,
 }
 
 impl AudioClip {
     pub fn new(
         name: &str,
+        path: &str,
         buffer: &std::cell::RefCell<sfml::SfBox<sfml::audio::SoundBuffer>>,
     ) -> Self {
         AudioClip {
             name: name.to_string(),
+            path: path.to_string(),
             sound: Sound::new(buffer),
         }
     }
+
+    /// Starts the clip unless it is already playing
+    pub fn play(&mut self) {
+        let sound = self.sound.get_sound();
+        if sound.status() == SoundStatus::STOPPED {
+            sound.play();
+        }
+    }
 }
 
 impl Component for AudioClip {
@@ -25,6 +39,11 @@ impl Component for AudioClip {
         &self.name
     }
 
+    /// Clips removed from their Entity stop playing instead of running out in the background
+    fn on_detach(&mut self, _e: &mut Entity) {
+        self.sound.get_sound().stop();
+    }
+
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
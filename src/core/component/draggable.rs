@@ -0,0 +1,130 @@
+use crate::core::component::Component;
+use crate::util::rect::Rect;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// Axes a Draggable Entity may move along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DragAxis {
+    #[default]
+    Both,
+    X,
+    Y,
+}
+
+/// Lets the pointer drag an Entity by its Transform, see `core::drag`. The Entity must also be
+/// Pickable so presses can find it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Draggable {
+    /// Distance in pixels the pointer has to travel before a press turns into a drag
+    pub threshold: f32,
+    pub axis: DragAxis,
+
+    /// Area the Entity's position is kept inside of, in its parent's space
+    pub bounds: Option<Rect>,
+
+    /// Kind of payload offered to drop targets, eg: "item"
+    pub payload: String,
+}
+
+impl Default for Draggable {
+    fn default() -> Self {
+        Self {
+            threshold: 4.,
+            axis: DragAxis::Both,
+            bounds: None,
+            payload: "".to_string(),
+        }
+    }
+}
+
+impl Draggable {
+    /// Applies the axis lock and bounds to a position, `start` is where the drag began
+    pub fn constrain(&self, start: (f32, f32), position: (f32, f32)) -> (f32, f32) {
+        let (mut x, mut y) = position;
+        match self.axis {
+            DragAxis::X => y = start.1,
+            DragAxis::Y => x = start.0,
+            DragAxis::Both => {}
+        }
+
+        if let Some(b) = self.bounds {
+            x = x.max(b.x).min(b.x + b.w);
+            y = y.max(b.y).min(b.y + b.h);
+        }
+
+        (x, y)
+    }
+}
+
+pub static DRAGGABLE_NAME: &str = "__draggable__";
+
+impl Component for Draggable {
+    fn get_name(&self) -> &str {
+        DRAGGABLE_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_drags_move_along_both_axes() {
+        let draggable = Draggable::default();
+        assert_eq!(draggable.constrain((0., 0.), (5., -3.)), (5., -3.));
+    }
+
+    #[test]
+    fn axis_lock_keeps_the_other_axis_at_the_start() {
+        let x_only = Draggable {
+            axis: DragAxis::X,
+            ..Default::default()
+        };
+        assert_eq!(x_only.constrain((1., 2.), (5., 7.)), (5., 2.));
+
+        let y_only = Draggable {
+            axis: DragAxis::Y,
+            ..Default::default()
+        };
+        assert_eq!(y_only.constrain((1., 2.), (5., 7.)), (1., 7.));
+    }
+
+    #[test]
+    fn bounds_clamp_the_position() {
+        let bounded = Draggable {
+            bounds: Some(Rect {
+                x: 10.,
+                y: 20.,
+                w: 100.,
+                h: 50.,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(bounded.constrain((0., 0.), (50., 40.)), (50., 40.));
+        assert_eq!(bounded.constrain((0., 0.), (0., 0.)), (10., 20.));
+        assert_eq!(bounded.constrain((0., 0.), (500., 500.)), (110., 70.));
+    }
+
+    #[test]
+    fn bounds_apply_after_the_axis_lock() {
+        let draggable = Draggable {
+            axis: DragAxis::X,
+            bounds: Some(Rect {
+                x: 0.,
+                y: 0.,
+                w: 10.,
+                h: 10.,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(draggable.constrain((5., 50.), (20., 0.)), (10., 10.));
+    }
+}
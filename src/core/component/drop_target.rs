@@ -0,0 +1,55 @@
+use crate::core::component::Component;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// Lets Draggable Entities be dropped on an Entity, see `core::drag`. The Entity is hit where its
+/// own Image and Text components are rendered.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DropTarget {
+    /// Payload kinds which may be dropped, empty accepts every kind
+    pub accepts: Vec<String>,
+}
+
+impl DropTarget {
+    pub fn accepts(&self, payload: &str) -> bool {
+        self.accepts.is_empty() || self.accepts.iter().any(|a| a == payload)
+    }
+}
+
+pub static DROP_TARGET_NAME: &str = "__drop_target__";
+
+impl Component for DropTarget {
+    fn get_name(&self) -> &str {
+        DROP_TARGET_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_listed_kinds_only() {
+        let target = DropTarget {
+            accepts: vec!["card".to_string(), "coin".to_string()],
+        };
+        assert!(target.accepts("card"));
+        assert!(target.accepts("coin"));
+        assert!(!target.accepts("book"));
+        assert!(!target.accepts(""));
+    }
+
+    #[test]
+    fn empty_list_accepts_everything() {
+        let target = DropTarget::default();
+        assert!(target.accepts("card"));
+        assert!(target.accepts(""));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::core::component::Component;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// Lets an Entity take keyboard focus, see `core::focus`. Focusable Entities which are also
+/// Pickable are focused when clicked.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Focusable {
+    /// Position in the Tab focus chain, lower first. Ties keep tree order.
+    pub tab_index: i32,
+}
+
+pub static FOCUSABLE_NAME: &str = "__focusable__";
+
+impl Component for Focusable {
+    fn get_name(&self) -> &str {
+        FOCUSABLE_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,186 @@
+use crate::core::component::transform::transform_point;
+use crate::core::renderer::app_gl::Texture;
+use crate::core::resource::Resources;
+use crate::util::rect::Rect;
+use crate::util::scale::Scale;
+
+extern crate nalgebra_glm as glm;
+
+/// Texture a HitQuad samples for alpha picking
+#[derive(Debug, Clone, Copy)]
+pub struct HitMask {
+    pub texture: Texture,
+
+    /// Section of the texture drawn on the quad in texels, None for the whole texture
+    pub uv_rect: Option<Rect>,
+
+    /// RGBA channel holding coverage, 3 for images and 0 for text
+    pub channel: usize,
+}
+
+/// Quad covered by a rendered component, described the same way as its MVPConfig so hit testing
+/// matches what is on screen
+#[derive(Debug, Clone, Copy)]
+pub struct HitQuad {
+    pub rect: Rect,
+    pub rotation: f32,
+    pub scale: Scale,
+    pub alpha: f32,
+    pub mask: Option<HitMask>,
+}
+
+impl HitQuad {
+    /// Maps the unit quad, centered on the origin, into the local space of the Entity. Unlike the
+    /// render model this stays y-down so quad coordinates line up with texture rows.
+    fn model(&self) -> glm::TMat4<f32> {
+        let id = glm::identity::<f32, 4>();
+        let translate = glm::translate(&id, &glm::make_vec3(&[self.rect.x, self.rect.y, 0.]));
+        let rotate = glm::rotate(&id, -self.rotation, &glm::make_vec3(&[0., 0., 1.]));
+        let scale = glm::scale(
+            &id,
+            &glm::make_vec3(&[self.rect.w * self.scale.x, self.rect.h * self.scale.y, 1.]),
+        );
+
+        translate * rotate * scale
+    }
+
+    /// Whether the screen space `point` lands on the quad drawn with `world`. With an
+    /// `alpha_threshold` the texel under the point must also be at least that opaque.
+    pub fn hit(
+        &self,
+        resources: &Resources,
+        world: &glm::TMat4<f32>,
+        point: (f32, f32),
+        alpha_threshold: Option<f32>,
+    ) -> bool {
+        // Zero sized quads and transforms have no inverse and can never be hit
+        let inverse = match (world * self.model()).try_inverse() {
+            Some(inverse) => inverse,
+            None => return false,
+        };
+
+        let (qx, qy) = transform_point(&inverse, point);
+        if !(-0.5..=0.5).contains(&qx) || !(-0.5..=0.5).contains(&qy) {
+            return false;
+        }
+
+        match (alpha_threshold, self.mask) {
+            (Some(threshold), Some(mask)) => {
+                self.alpha * mask.coverage(resources, qx + 0.5, qy + 0.5) >= threshold
+            }
+            (Some(threshold), None) => self.alpha >= threshold,
+            _ => true,
+        }
+    }
+}
+
+impl HitMask {
+    /// Coverage in the range 0..1 at quad coordinates in the range 0..1
+    fn coverage(&self, resources: &Resources, u: f32, v: f32) -> f32 {
+        let (width, height) = (self.texture.width, self.texture.height);
+        if width == 0 || height == 0 {
+            return 0.;
+        }
+
+        let uv_rect = self.uv_rect.unwrap_or(Rect {
+            x: 0.,
+            y: 0.,
+            w: width as f32,
+            h: height as f32,
+        });
+        let tx = ((uv_rect.x + u * uv_rect.w) as u32).min(width - 1);
+        let ty = ((uv_rect.y + v * uv_rect.h) as u32).min(height - 1);
+
+        let pixels = resources.texture_pixels(&self.texture);
+        let offset = ((ty * width + tx) * 4) as usize + self.channel;
+        pixels.get(offset).map_or(0., |a| *a as f32 / 255.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f32::consts::FRAC_PI_2;
+
+    /// 40x10 quad centered on the origin
+    fn quad() -> HitQuad {
+        HitQuad {
+            rect: Rect {
+                x: 0.,
+                y: 0.,
+                w: 40.,
+                h: 10.,
+            },
+            rotation: 0.,
+            scale: Scale::default(),
+            alpha: 1.,
+            mask: None,
+        }
+    }
+
+    fn hits(quad: &HitQuad, world: &glm::TMat4<f32>, point: (f32, f32)) -> bool {
+        quad.hit(&Resources::default(), world, point, None)
+    }
+
+    #[test]
+    fn hits_inside_the_quad_only() {
+        let world = glm::identity::<f32, 4>();
+        assert!(hits(&quad(), &world, (15., 4.)));
+        assert!(!hits(&quad(), &world, (25., 0.)));
+        assert!(!hits(&quad(), &world, (0., 6.)));
+    }
+
+    #[test]
+    fn rotation_turns_the_quad() {
+        let rotated = HitQuad {
+            rotation: FRAC_PI_2,
+            ..quad()
+        };
+        let world = glm::identity::<f32, 4>();
+        assert!(hits(&rotated, &world, (0., 15.)));
+        assert!(!hits(&rotated, &world, (15., 0.)));
+    }
+
+    #[test]
+    fn scale_grows_the_quad() {
+        let scaled = HitQuad {
+            scale: Scale { x: 2., y: 1. },
+            ..quad()
+        };
+        let world = glm::identity::<f32, 4>();
+        assert!(hits(&scaled, &world, (35., 0.)));
+        assert!(!hits(&scaled, &world, (45., 0.)));
+    }
+
+    #[test]
+    fn world_transform_moves_and_scales_the_quad() {
+        let id = glm::identity::<f32, 4>();
+        let world = glm::translate(&id, &glm::make_vec3(&[100., 50., 0.]))
+            * glm::scale(&id, &glm::make_vec3(&[1., 3., 1.]));
+        assert!(hits(&quad(), &world, (110., 62.)));
+        assert!(!hits(&quad(), &world, (10., 2.)));
+        assert!(!hits(&quad(), &world, (110., 68.)));
+    }
+
+    #[test]
+    fn degenerate_quads_are_never_hit() {
+        let flat = HitQuad {
+            scale: Scale { x: 0., y: 1. },
+            ..quad()
+        };
+        assert!(!hits(&flat, &glm::identity::<f32, 4>(), (0., 0.)));
+    }
+
+    #[test]
+    fn alpha_threshold_without_a_mask_uses_the_quad_alpha() {
+        let faded = HitQuad {
+            alpha: 0.3,
+            ..quad()
+        };
+        let world = glm::identity::<f32, 4>();
+        let resources = Resources::default();
+        assert!(!faded.hit(&resources, &world, (0., 0.), Some(0.5)));
+        assert!(faded.hit(&resources, &world, (0., 0.), Some(0.2)));
+    }
+}
@@ -1,6 +1,10 @@
 use crate::app::App;
+use crate::core::component::hit::{HitMask, HitQuad};
 use crate::core::component::Component;
 use crate::core::renderer::app_gl::Texture;
+use crate::core::renderer::atlas::AtlasRegion;
+use crate::core::renderer::batch::{uv_rect, BatchKey, BatchQuad, BlendMode};
+use crate::core::renderer::material::Material;
 use crate::core::renderer::MVPConfig;
 
 use crate::util::alpha::Alpha;
@@ -8,15 +12,13 @@ use crate::util::color::Color;
 use crate::util::rect::Rect;
 use crate::util::scale::Scale;
 
-use core::ffi::c_void;
-
-use gl::*;
+use serde::{Deserialize, Serialize};
 
 extern crate nalgebra_glm as glm;
 
 use core::any::Any;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ImageRenderType {
     Nearest,
     Linear,
@@ -40,7 +42,15 @@ pub struct Image {
     // Optional Section of the image to render in screen space
     pub r_rect: Option<Rect>,
 
+    /// Section of `texture` holding the image when it was packed into an atlas page. `r_rect` is
+    /// relative to it.
+    pub region: Option<Rect>,
+
     pub render_type: Option<ImageRenderType>,
+    pub blend: BlendMode,
+
+    /// Shader to draw the image with instead of the default one
+    pub material: Option<Material>,
 }
 
 impl Image {
@@ -61,6 +71,30 @@ impl Image {
         }
     }
 
+    pub fn with_region(name: &str, region: &AtlasRegion) -> Self {
+        Image {
+            name: name.to_string(),
+            texture: Some(region.texture),
+            region: Some(region.rect),
+            width: region.rect.w,
+            height: region.rect.h,
+            ..Default::default()
+        }
+    }
+
+    /// Section of the texture to render in its pixels, None for the whole texture
+    pub fn source_rect(&self) -> Option<Rect> {
+        match (self.region, self.r_rect) {
+            (Some(region), Some(r)) => Some(Rect {
+                x: region.x + r.x,
+                y: region.y + r.y,
+                w: r.w,
+                h: r.h,
+            }),
+            (region, r) => r.or(region),
+        }
+    }
+
     pub fn apply_image(
         &mut self,
         image_load_event_payload: &crate::core::event::ImageLoadEventPayload,
@@ -70,6 +104,7 @@ impl Image {
             width: image_load_event_payload.width,
             height: image_load_event_payload.height,
         });
+        self.region = None;
         self.width = image_load_event_payload.width as f32;
         self.height = image_load_event_payload.height as f32;
     }
@@ -84,83 +119,58 @@ impl Component for Image {
         self.zindex
     }
 
-    fn render(&self, app: &App, parent_offset: (f32, f32)) {
+    fn hit_quad(&self) -> Option<HitQuad> {
+        let texture = self.texture?;
+        Some(HitQuad {
+            rect: Rect {
+                x: self.x,
+                y: self.y,
+                w: self.width,
+                h: self.height,
+            },
+            rotation: self.rotation,
+            scale: self.scale,
+            alpha: self.alpha.val,
+            mask: Some(HitMask {
+                texture,
+                uv_rect: self.source_rect(),
+                channel: 3,
+            }),
+        })
+    }
+
+    fn render(&self, app: &App, world: &glm::TMat4<f32>) {
         if let Some(texture) = self.texture {
-            let mvp = app.renderer.make_mvp(&MVPConfig {
-                rect: Rect {
-                    x: self.x + parent_offset.0,
-                    y: self.y + parent_offset.1,
-                    w: self.width,
-                    h: self.height,
+            let mvp = app.renderer.make_mvp(
+                world,
+                &MVPConfig {
+                    rect: Rect {
+                        x: self.x,
+                        y: self.y,
+                        w: self.width,
+                        h: self.height,
+                    },
+                    rotation: self.rotation,
+                    scale: self.scale,
+                },
+            );
+
+            app.renderer.push_quad(
+                BatchKey {
+                    texture_id: texture.texture_id,
+                    program: app.renderer.gl.image_program,
+                    blend: self.blend,
+                    filter: self.render_type,
+                    material: 0,
+                },
+                self.material.as_ref(),
+                &BatchQuad {
+                    mvp,
+                    uv_rect: uv_rect(&texture, self.source_rect()),
+                    color: self.color,
+                    alpha: self.alpha.val,
                 },
-                rotation: self.rotation,
-                scale: self.scale,
-            });
-
-            unsafe {
-                Enable(BLEND);
-                BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
-                BindVertexArray(app.renderer.gl.vao);
-                BindBuffer(ELEMENT_ARRAY_BUFFER, app.renderer.gl.ebo);
-                UseProgram(app.renderer.gl.image_program.id);
-
-                UniformMatrix4fv(
-                    app.renderer.gl.image_program.mvp_loc,
-                    1,
-                    FALSE,
-                    mvp.data.as_slice().as_ptr(),
-                );
-
-                Uniform4f(
-                    app.renderer.gl.image_program.color_loc,
-                    self.color.r,
-                    self.color.g,
-                    self.color.b,
-                    self.alpha.val,
-                );
-
-                match &self.r_rect {
-                    Some(r) => {
-                        // Convert screen space rect to render space
-                        // 256, 256 ,256 ,256 => (0.5, 0.5, 0.5, 0.5) @ 512x512
-                        let x = r.x / texture.width as f32;
-                        let y = r.y / texture.height as f32;
-                        let w = r.w / texture.width as f32;
-                        let h = r.h / texture.height as f32;
-                        Uniform4f(app.renderer.gl.image_program.uv_rect_loc, x, y, w, h);
-                    }
-                    _ => {
-                        // Default rect which renders the whole image
-                        Uniform4f(
-                            app.renderer.gl.image_program.uv_rect_loc,
-                            0.0,
-                            0.0,
-                            1.0,
-                            1.0,
-                        );
-                    }
-                };
-
-                BindTexture(TEXTURE_2D, texture.texture_id);
-
-                if let Some(t) = &self.render_type {
-                    let min_max: (i32, i32) = match t {
-                        ImageRenderType::Linear => (
-                            NEAREST_MIPMAP_NEAREST.try_into().unwrap(),
-                            NEAREST.try_into().unwrap(),
-                        ),
-
-                        _ => (
-                            NEAREST_MIPMAP_NEAREST.try_into().unwrap(),
-                            NEAREST.try_into().unwrap(),
-                        ),
-                    };
-                    TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, min_max.0);
-                    TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, min_max.1);
-                }
-
-                DrawElements(TRIANGLES, 6, UNSIGNED_INT, std::ptr::null::<c_void>());
-            }
+            );
         }
     }
 
@@ -1,18 +1,60 @@
 pub mod audio_clip;
+pub mod draggable;
+pub mod drop_target;
+pub mod focusable;
+pub mod hit;
 pub mod image;
-pub mod offset;
+pub mod pickable;
 pub mod pre_frame;
+pub mod render_target;
 pub mod text;
+pub mod transform;
 
 use std::any::Any;
 
 use crate::app::App;
+use crate::core::component::hit::HitQuad;
+use crate::core::entity::Entity;
+use crate::core::event::{Event, EventContext};
 
-pub trait Component {
+extern crate nalgebra_glm as glm;
+
+/// Read only access to a component's concrete type. Implemented for every Component.
+pub trait ComponentAny {
+    fn as_any_ref(&self) -> &dyn Any;
+}
+
+impl<T: Component + 'static> ComponentAny for T {
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub trait Component: ComponentAny {
     fn get_name(&self) -> &str;
     fn z_index(&self) -> i32 {
         0
     }
-    fn render(&self, _app: &App, _parent_offset: (f32, f32)) {}
+    /// Render the component. `world` maps the owning Entity's local space into screen space.
+    fn render(&self, _app: &App, _world: &glm::TMat4<f32>) {}
+
+    /// Quad the component covers when rendered, in the owning Entity's local space. Used for
+    /// pointer hit testing of Pickable Entities.
+    fn hit_quad(&self) -> Option<HitQuad> {
+        None
+    }
+
+    /// Called once per frame with the deltatime from the previous frame, after the Entity's behavior
+    fn update(&mut self, _app: &App, _dt: f32) {}
+
+    /// Called in the bubble phase after the owning Entity's children and before its behavior
+    fn on_event(&mut self, _app: &mut Option<&mut App>, _ev: &Event, _ctx: &mut EventContext) {}
+
+    /// Called when the component is added to an Entity, right before it is inserted
+    fn on_attach(&mut self, _e: &mut Entity) {}
+
+    /// Called after the component has been removed from an Entity
+    fn on_detach(&mut self, _e: &mut Entity) {}
+
     fn as_any(&mut self) -> &mut dyn Any;
 }
@@ -0,0 +1,26 @@
+use crate::core::component::Component;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// Marks an Entity as a pointer target. The Entity is hit where its own Image and Text
+/// components are rendered, see `core::pointer`.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pickable {
+    /// When set, pixels whose rendered alpha is below the threshold are not hit
+    pub alpha_threshold: Option<f32>,
+}
+
+pub static PICKABLE_NAME: &str = "__pickable__";
+
+impl Component for Pickable {
+    fn get_name(&self) -> &str {
+        PICKABLE_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
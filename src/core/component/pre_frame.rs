@@ -3,6 +3,8 @@ use crate::core::component::Component;
 
 use gl::*;
 
+extern crate nalgebra_glm as glm;
+
 use core::any::Any;
 
 #[derive(Default, Debug)]
@@ -13,7 +15,7 @@ impl Component for PreFrame {
         "__pre_frame__"
     }
 
-    fn render(&self, app: &App, _parent_offset: (f32, f32)) {
+    fn render(&self, app: &App, _world: &glm::TMat4<f32>) {
         unsafe {
             Clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
             Viewport(
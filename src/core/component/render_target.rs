@@ -0,0 +1,130 @@
+use crate::app::App;
+use crate::core::component::Component;
+use crate::core::renderer::app_gl::{
+    create_framebuffer, forget_texture_pixels, release_framebuffer, release_texture, Texture,
+};
+use crate::core::renderer::TargetSpace;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// When a RenderTarget renders its subtree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RedrawMode {
+    #[default]
+    EveryFrame,
+
+    /// Only after `request_redraw`, eg: for cached static backgrounds
+    OnDemand,
+}
+
+/// Renders the owning Entity's subtree into an offscreen texture instead of the window. The
+/// subtree is rendered in the target's own space, y-down with the origin at the top left, and
+/// is not hit by the pointer. Show the result with an Image using `texture`.
+#[derive(Debug)]
+pub struct RenderTarget {
+    fbo: u32,
+    texture: Texture,
+    pub redraw: RedrawMode,
+    dirty: bool,
+}
+
+pub static RENDER_TARGET_NAME: &str = "__render_target__";
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32, redraw: RedrawMode) -> Result<Self, String> {
+        let (fbo, texture) = create_framebuffer(width, height)?;
+        Ok(Self {
+            fbo,
+            texture,
+            redraw,
+            dirty: true,
+        })
+    }
+
+    /// Texture the subtree is rendered into, owned by the RenderTarget
+    pub fn texture(&self) -> Texture {
+        self.texture
+    }
+
+    /// Renders the subtree again on the next frame when redrawing on demand
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn request_redraw(&mut self) {
+        self.dirty = true;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.redraw == RedrawMode::EveryFrame || self.dirty
+    }
+
+    /// Starts rendering into the target, false when it does not need to be redrawn
+    pub fn begin(&mut self, app: &App) -> bool {
+        if !self.needs_redraw() {
+            return false;
+        }
+
+        // Picking has to read the new frame back
+        self.dirty = false;
+        forget_texture_pixels(self.texture.texture_id);
+        app.renderer.push_target(TargetSpace {
+            fbo: self.fbo,
+            width: self.texture.width as f32,
+            height: self.texture.height as f32,
+        });
+        true
+    }
+
+    pub fn end(&self, app: &App) {
+        app.renderer.pop_target();
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        release_framebuffer(self.fbo);
+        release_texture(self.texture.texture_id);
+    }
+}
+
+impl Component for RenderTarget {
+    fn get_name(&self) -> &str {
+        RENDER_TARGET_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Target without a framebuffer, forgotten after use as there is no GL context to release it
+    fn target(redraw: RedrawMode) -> RenderTarget {
+        RenderTarget {
+            fbo: 0,
+            texture: Texture::default(),
+            redraw,
+            dirty: false,
+        }
+    }
+
+    #[test]
+    fn every_frame_always_redraws() {
+        let target = target(RedrawMode::EveryFrame);
+        assert!(target.needs_redraw());
+        std::mem::forget(target);
+    }
+
+    #[test]
+    fn on_demand_redraws_only_when_requested() {
+        let mut target = target(RedrawMode::OnDemand);
+        assert!(!target.needs_redraw());
+
+        target.request_redraw();
+        assert!(target.needs_redraw());
+        std::mem::forget(target);
+    }
+}
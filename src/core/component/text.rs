@@ -1,5 +1,9 @@
 use crate::app::App;
+use crate::core::component::hit::{HitMask, HitQuad};
 use crate::core::component::Component;
+use crate::core::renderer::app_gl::{release_texture, Texture};
+use crate::core::renderer::batch::{BatchKey, BatchQuad, BlendMode};
+use crate::core::renderer::material::Material;
 use crate::core::renderer::MVPConfig;
 use crate::core::resource::TextLoadInfo;
 
@@ -8,9 +12,6 @@ use crate::util::color::Color;
 use crate::util::rect::Rect;
 use crate::util::scale::Scale;
 
-use core::ffi::c_void;
-
-use gl::*;
 extern crate nalgebra_glm as glm;
 
 use core::any::Any;
@@ -20,6 +21,8 @@ pub struct Text {
     pub name: String,
     pub zindex: i32,
     pub text: String,
+    pub font_path: String,
+    pub font_size: isize,
     pub texture_id: Option<u32>,
     pub x: i32,
     pub y: i32,
@@ -28,14 +31,20 @@ pub struct Text {
     pub height: u32,
     pub color: Color,
     pub alpha: Alpha,
+
+    /// Shader to draw the text with instead of the default one. Glyph coverage is in the red
+    /// channel of the texture.
+    pub material: Option<Material>,
+
+    /// Whether `texture_id` was rendered for this Text alone, see `update_owned_text`
+    owns_texture: bool,
 }
 
 impl Text {
     pub fn new(name: &str) -> Self {
-        Text {
-            name: name.to_string(),
-            ..Default::default()
-        }
+        let mut text = Text::default();
+        text.name = name.to_string();
+        text
     }
 
     pub fn new_with_text(name: &str, app: &mut App, t: &str) -> Self {
@@ -53,9 +62,47 @@ impl Text {
 
     pub fn update_text(&mut self, app: &mut App, text_load_info: &TextLoadInfo) {
         let text_texture = app.resource.load_text_texture(text_load_info).unwrap();
-        self.texture_id = Some(text_texture.texture_id);
-        self.width = text_texture.width;
-        self.height = text_texture.height;
+        self.set_texture(text_load_info, text_texture, false);
+    }
+
+    /// Renders into a texture owned by this Text, released when the text changes again or the
+    /// Text is dropped. `update_text` keeps every string it rendered, this suits text which
+    /// changes often, eg: typed input.
+    pub fn update_owned_text(&mut self, app: &mut App, text_load_info: &TextLoadInfo) {
+        match app.resource.render_text_texture(text_load_info) {
+            Ok(text_texture) => self.set_texture(text_load_info, text_texture, true),
+            Err(err) => println!("Failed to render text {:?}: {}", text_load_info.text, err),
+        }
+    }
+
+    /// Removes the text, the Text renders nothing until it is updated again
+    pub fn clear(&mut self) {
+        self.release_texture();
+        self.text.clear();
+    }
+
+    fn set_texture(&mut self, text_load_info: &TextLoadInfo, texture: Texture, owned: bool) {
+        self.release_texture();
+        self.text = text_load_info.text.clone();
+        self.font_path = text_load_info.font_path.clone();
+        self.font_size = text_load_info.font_size;
+        self.texture_id = Some(texture.texture_id);
+        self.width = texture.width;
+        self.height = texture.height;
+        self.owns_texture = owned;
+    }
+
+    fn release_texture(&mut self) {
+        if let (Some(texture_id), true) = (self.texture_id.take(), self.owns_texture) {
+            release_texture(texture_id);
+        }
+        self.owns_texture = false;
+    }
+}
+
+impl Drop for Text {
+    fn drop(&mut self) {
+        self.release_texture();
     }
 }
 
@@ -68,41 +115,68 @@ impl Component for Text {
         self.zindex
     }
 
-    fn render(&self, app: &App, parent_offset: (f32, f32)) {
+    fn hit_quad(&self) -> Option<HitQuad> {
+        let texture_id = self.texture_id?;
+        Some(HitQuad {
+            rect: Rect {
+                x: self.x as f32,
+                y: self.y as f32,
+                w: self.width as f32,
+                h: self.height as f32,
+            },
+            rotation: self.rotation,
+            scale: Scale { x: 1., y: 1. },
+            alpha: self.alpha.val,
+            // Glyph coverage is stored in the red channel
+            mask: Some(HitMask {
+                texture: Texture {
+                    texture_id,
+                    width: self.width,
+                    height: self.height,
+                },
+                uv_rect: None,
+                channel: 0,
+            }),
+        })
+    }
+
+    fn render(&self, app: &App, world: &glm::TMat4<f32>) {
         if let Some(tid) = self.texture_id {
-            let mvp = app.renderer.make_mvp(&MVPConfig {
-                rect: Rect {
-                    x: self.x as f32 + parent_offset.0,
-                    y: self.y as f32 + parent_offset.1,
-                    w: self.width as f32,
-                    h: self.height as f32,
+            let mvp = app.renderer.make_mvp(
+                world,
+                &MVPConfig {
+                    rect: Rect {
+                        x: self.x as f32,
+                        y: self.y as f32,
+                        w: self.width as f32,
+                        h: self.height as f32,
+                    },
+                    rotation: self.rotation,
+                    scale: Scale { x: 1., y: 1. },
+                },
+            );
+
+            app.renderer.push_quad(
+                BatchKey {
+                    texture_id: tid,
+                    program: app.renderer.gl.text_program,
+                    blend: BlendMode::Alpha,
+                    filter: None,
+                    material: 0,
+                },
+                self.material.as_ref(),
+                &BatchQuad {
+                    mvp,
+                    uv_rect: Rect {
+                        x: 0.,
+                        y: 0.,
+                        w: 1.,
+                        h: 1.,
+                    },
+                    color: self.color,
+                    alpha: self.alpha.val,
                 },
-                rotation: self.rotation,
-                scale: Scale { x: 1., y: 1. },
-            });
-
-            unsafe {
-                Enable(BLEND);
-                BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
-                BindVertexArray(app.renderer.gl.vao);
-                BindBuffer(ELEMENT_ARRAY_BUFFER, app.renderer.gl.ebo);
-                UseProgram(app.renderer.gl.text_program.id);
-                UniformMatrix4fv(
-                    app.renderer.gl.text_program.mvp_loc,
-                    1,
-                    FALSE,
-                    mvp.data.as_slice().as_ptr(),
-                );
-                Uniform4f(
-                    app.renderer.gl.text_program.color_loc,
-                    self.color.r,
-                    self.color.g,
-                    self.color.b,
-                    self.alpha.val,
-                );
-                BindTexture(TEXTURE_2D, tid);
-                DrawElements(TRIANGLES, 6, UNSIGNED_INT, std::ptr::null::<c_void>());
-            }
+            );
         }
     }
 
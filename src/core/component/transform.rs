@@ -0,0 +1,148 @@
+use crate::core::component::Component;
+use crate::util::scale::Scale;
+
+extern crate nalgebra_glm as glm;
+
+use core::any::Any;
+
+use serde::{Deserialize, Serialize};
+
+/// Local transform of an Entity relative to its parent. Rotation and scale are applied around
+/// the pivot, which is expressed in the Entity's local space.
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub scale: Scale,
+    pub pivot: (f32, f32),
+}
+
+pub static TRANSFORM_NAME: &str = "__transform__";
+
+impl Transform {
+    /// Matrix mapping this Entity's local space into its parent's space
+    pub fn local_matrix(&self) -> glm::TMat4<f32> {
+        let id = glm::identity::<f32, 4>();
+        let translate = glm::translate(
+            &id,
+            &glm::make_vec3(&[self.x + self.pivot.0, self.y + self.pivot.1, 0.]),
+        );
+
+        // Screen space is y-down, negate so positive rotation matches Image/Text rotation
+        let rotate = glm::rotate(&id, -self.rotation, &glm::make_vec3(&[0., 0., 1.]));
+        let scale = glm::scale(&id, &glm::make_vec3(&[self.scale.x, self.scale.y, 1.]));
+        let unpivot = glm::translate(&id, &glm::make_vec3(&[-self.pivot.0, -self.pivot.1, 0.]));
+
+        translate * rotate * scale * unpivot
+    }
+}
+
+impl Component for Transform {
+    fn get_name(&self) -> &str {
+        TRANSFORM_NAME
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Maps a point from the space described by `m` into screen space
+pub fn transform_point(m: &glm::TMat4<f32>, p: (f32, f32)) -> (f32, f32) {
+    let v = m * glm::make_vec4(&[p.0, p.1, 0., 1.]);
+    (v.x, v.y)
+}
+
+/// Maps a screen space point into the space described by `m`
+pub fn inverse_transform_point(m: &glm::TMat4<f32>, p: (f32, f32)) -> (f32, f32) {
+    transform_point(&glm::inverse(m), p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::core::entity::Entity;
+
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn transform(x: f32, y: f32, rotation: f32, scale: (f32, f32), pivot: (f32, f32)) -> Transform {
+        Transform {
+            x,
+            y,
+            rotation,
+            scale: Scale {
+                x: scale.0,
+                y: scale.1,
+            },
+            pivot,
+        }
+    }
+
+    #[test]
+    fn translates() {
+        let m = transform(10., 20., 0., (1., 1.), (0., 0.)).local_matrix();
+        assert_near(transform_point(&m, (1., 2.)), (11., 22.));
+        assert_near(inverse_transform_point(&m, (11., 22.)), (1., 2.));
+    }
+
+    #[test]
+    fn rotates_clockwise_on_screen() {
+        // Screen space is y-down, so +x turns towards -y
+        let m = transform(0., 0., FRAC_PI_2, (1., 1.), (0., 0.)).local_matrix();
+        assert_near(transform_point(&m, (1., 0.)), (0., -1.));
+    }
+
+    #[test]
+    fn scales_before_rotating() {
+        let m = transform(0., 0., FRAC_PI_2, (2., 1.), (0., 0.)).local_matrix();
+        assert_near(transform_point(&m, (1., 0.)), (0., -2.));
+    }
+
+    #[test]
+    fn rotates_and_scales_around_the_pivot() {
+        let m = transform(100., 50., FRAC_PI_2, (2., 2.), (10., 0.)).local_matrix();
+
+        // The pivot only moves with the translation
+        assert_near(transform_point(&m, (10., 0.)), (110., 50.));
+        assert_near(transform_point(&m, (11., 0.)), (110., 48.));
+        assert_near(transform_point(&m, (0., 0.)), (110., 70.));
+    }
+
+    #[test]
+    fn inverse_undoes_the_whole_transform() {
+        let m = transform(-30., 12., 0.7, (1.5, 0.5), (4., -6.)).local_matrix();
+        for p in [(0., 0.), (4., -6.), (25., 13.)] {
+            assert_near(inverse_transform_point(&m, transform_point(&m, p)), p);
+        }
+    }
+
+    #[test]
+    fn children_are_placed_in_their_parent_space() {
+        let mut child = Entity::new("child");
+        child.add_component(transform(10., 0., 0., (1., 1.), (0., 0.)));
+        let mut parent = Entity::new("parent");
+        parent.add_component(transform(100., 0., FRAC_PI_2, (2., 2.), (0., 0.)));
+        parent.add_child(child);
+
+        parent.render_components(&App::default(), &glm::identity());
+        let child = parent.children_mut().next().unwrap();
+        let world = child.world_transform();
+
+        // The child's offset is scaled and rotated by the parent
+        assert_near(transform_point(world, (0., 0.)), (100., -20.));
+        assert_near(transform_point(world, (1., 0.)), (100., -22.));
+        assert_near(inverse_transform_point(world, (100., -22.)), (1., 0.));
+    }
+}
@@ -0,0 +1,505 @@
+use crate::core::component::draggable::Draggable;
+use crate::core::component::drop_target::DropTarget;
+use crate::core::component::transform::{inverse_transform_point, Transform, TRANSFORM_NAME};
+use crate::core::entity::pick::{pick_with, PointerHit};
+use crate::core::entity::{Entity, EntityId};
+use crate::core::event::{DragPayload, DropPayload, Event};
+use crate::core::resource::Resources;
+
+use sfml::window::mouse::Button;
+use sfml::window::Event as SFMLEvent;
+
+extern crate nalgebra_glm as glm;
+
+/// Press on a Draggable Entity which may turn into a drag
+#[derive(Debug)]
+struct ActiveDrag {
+    entity: EntityId,
+    draggable: Draggable,
+
+    /// Screen space position of the press
+    origin: (f32, f32),
+
+    /// Entity position when pressed and its current one, in its parent's space
+    start: (f32, f32),
+    position: (f32, f32),
+
+    /// Offset from the pointer to the Entity position, in its parent's space
+    grab: (f32, f32),
+
+    /// False until the pointer moves past the threshold
+    dragging: bool,
+
+    /// Drop target under the pointer and whether it accepts the payload
+    target: Option<(EntityId, bool)>,
+}
+
+impl ActiveDrag {
+    fn payload(&self, (x, y): (f32, f32)) -> DragPayload {
+        DragPayload {
+            entity: self.entity,
+            x,
+            y,
+            position: self.position,
+            target: self.target.map(|(t, _)| t),
+            accepted: matches!(self.target, Some((_, true))),
+        }
+    }
+
+    fn drop_payload(&self, target: EntityId, accepted: bool) -> DropPayload {
+        DropPayload {
+            source: self.entity,
+            target,
+            payload: self.draggable.payload.clone(),
+            accepted,
+        }
+    }
+}
+
+/// Turns left button presses on Draggable Entities into drag gestures, moving the Entity's
+/// Transform as the pointer moves and resolving DropTargets underneath it
+#[derive(Debug, Default)]
+pub struct Drag {
+    active: Option<ActiveDrag>,
+    pointer: (f32, f32),
+}
+
+impl Drag {
+    /// Entity being dragged, None while a press has not passed the threshold yet
+    pub fn dragging(&self) -> Option<EntityId> {
+        self.active
+            .as_ref()
+            .filter(|d| d.dragging)
+            .map(|d| d.entity)
+    }
+
+    /// Drag events produced by a window event, paired with the Entity they are delivered to.
+    /// `hit` is the Pickable Entity under the pointer.
+    pub fn handle_event(
+        &mut self,
+        root: &mut Entity,
+        resources: &Resources,
+        ev: &SFMLEvent,
+        hit: Option<PointerHit>,
+    ) -> Vec<(EntityId, Event)> {
+        let mut events = Vec::new();
+
+        match *ev {
+            SFMLEvent::MouseButtonPressed {
+                button: Button::Left,
+                x,
+                y,
+            } => {
+                self.pointer = (x as f32, y as f32);
+                self.active = hit.and_then(|hit| press(root, hit.id, self.pointer));
+            }
+            SFMLEvent::MouseMoved { x, y } => {
+                self.pointer = (x as f32, y as f32);
+                self.drag_to(root, resources, &mut events);
+            }
+            SFMLEvent::MouseButtonReleased {
+                button: Button::Left,
+                x,
+                y,
+            } => {
+                self.pointer = (x as f32, y as f32);
+                self.release(true, &mut events);
+            }
+            // The release would never arrive
+            SFMLEvent::LostFocus => self.release(false, &mut events),
+            _ => {}
+        }
+
+        events
+    }
+
+    fn drag_to(
+        &mut self,
+        root: &mut Entity,
+        resources: &Resources,
+        events: &mut Vec<(EntityId, Event)>,
+    ) {
+        let point = self.pointer;
+        let drag = match &mut self.active {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        if !drag.dragging {
+            let (dx, dy) = (point.0 - drag.origin.0, point.1 - drag.origin.1);
+            if (dx * dx + dy * dy).sqrt() < drag.draggable.threshold {
+                return;
+            }
+
+            drag.dragging = true;
+            events.push((drag.entity, Event::DragStart(drag.payload(point))));
+        }
+
+        {
+            let parent = parent_transform(root, drag.entity);
+            let e = match root.find_by_id(drag.entity) {
+                Ok(e) => e,
+                Err(_) => {
+                    // The Entity was removed mid drag
+                    self.active = None;
+                    return;
+                }
+            };
+
+            let pointer = inverse_transform_point(&parent, point);
+            let position = drag.draggable.constrain(
+                drag.start,
+                (pointer.0 + drag.grab.0, pointer.1 + drag.grab.1),
+            );
+            if let Some(transform) = e.get_named_mut::<Transform>(TRANSFORM_NAME) {
+                transform.x = position.0;
+                transform.y = position.1;
+            }
+            drag.position = position;
+        }
+
+        let target = pick_with(root, resources, point, Some(drag.entity), &|e| {
+            e.has::<DropTarget>()
+        })
+        .map(|hit| hit.id);
+
+        if target != drag.target.map(|(t, _)| t) {
+            if let Some((old, accepted)) = drag.target.take() {
+                let p = drag.drop_payload(old, accepted);
+                events.push((old, Event::DragLeave(p)));
+            }
+
+            if let Some(new) = target {
+                let accepted = match root.find_by_id(new) {
+                    Ok(t) => t
+                        .get::<DropTarget>()
+                        .is_some_and(|t| t.accepts(&drag.draggable.payload)),
+                    Err(_) => false,
+                };
+                drag.target = Some((new, accepted));
+                events.push((new, Event::DragEnter(drag.drop_payload(new, accepted))));
+            }
+        }
+
+        events.push((drag.entity, Event::DragMove(drag.payload(point))));
+    }
+
+    /// Ends the press, dropping on the target under the pointer when `drop` is set
+    fn release(&mut self, drop: bool, events: &mut Vec<(EntityId, Event)>) {
+        let mut drag = match self.active.take() {
+            Some(drag) if drag.dragging => drag,
+            _ => return,
+        };
+
+        match (drag.target, drop) {
+            (Some((target, true)), true) => {
+                events.push((target, Event::Drop(drag.drop_payload(target, true))));
+            }
+            (Some((target, accepted)), _) => {
+                events.push((
+                    target,
+                    Event::DragLeave(drag.drop_payload(target, accepted)),
+                ));
+            }
+            (None, _) => {}
+        }
+
+        // A cancelled drag was not dropped anywhere
+        if !drop {
+            drag.target = None;
+        }
+
+        events.push((drag.entity, Event::DragEnd(drag.payload(self.pointer))));
+    }
+}
+
+/// Starts tracking a press on `id` if it is Draggable. Entities without a Transform get one so
+/// there is something to move.
+fn press(root: &mut Entity, id: EntityId, point: (f32, f32)) -> Option<ActiveDrag> {
+    let parent = parent_transform(root, id);
+    let e = root.find_by_id(id).ok()?;
+    let draggable = e.get::<Draggable>()?.clone();
+
+    if e.get_named::<Transform>(TRANSFORM_NAME).is_none() {
+        e.add_component(Transform::default());
+    }
+    let transform = e.get_named::<Transform>(TRANSFORM_NAME)?;
+    let start = (transform.x, transform.y);
+
+    let pointer = inverse_transform_point(&parent, point);
+    Some(ActiveDrag {
+        entity: id,
+        draggable,
+        origin: point,
+        start,
+        position: start,
+        grab: (start.0 - pointer.0, start.1 - pointer.1),
+        dragging: false,
+        target: None,
+    })
+}
+
+/// World matrix of the Entity's parent from the most recent render, the space its Transform is
+/// expressed in
+fn parent_transform(root: &mut Entity, id: EntityId) -> glm::TMat4<f32> {
+    fn visit(e: &mut Entity, id: EntityId) -> Option<glm::TMat4<f32>> {
+        let world = *e.world_transform();
+        e.children_mut().find_map(|child| match child.id() == id {
+            true => Some(world),
+            false => visit(child, id),
+        })
+    }
+
+    visit(root, id).unwrap_or_else(glm::identity::<f32, 4>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::component::hit::HitQuad;
+    use crate::core::component::Component;
+    use crate::util::rect::Rect;
+    use crate::util::scale::Scale;
+
+    use core::any::Any;
+
+    /// Hit area standing in for a rendered Image
+    struct Area(Rect);
+
+    impl Component for Area {
+        fn get_name(&self) -> &str {
+            "area"
+        }
+
+        fn hit_quad(&self) -> Option<HitQuad> {
+            Some(HitQuad {
+                rect: self.0,
+                rotation: 0.,
+                scale: Scale::default(),
+                alpha: 1.,
+                mask: None,
+            })
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn area(x: f32, y: f32) -> Area {
+        Area(Rect {
+            x,
+            y,
+            w: 40.,
+            h: 40.,
+        })
+    }
+
+    fn drop_target(name: &str, x: f32, y: f32, accepts: &str) -> Entity {
+        let mut e = Entity::new(name);
+        e.add_component(area(x, y));
+        e.add_component(DropTarget {
+            accepts: vec![accepts.to_string()],
+        });
+        e
+    }
+
+    struct Scene {
+        root: Entity,
+        card: EntityId,
+        bin: EntityId,
+        shelf: EntityId,
+        resources: Resources,
+        drag: Drag,
+    }
+
+    /// A card at the origin, a bin taking cards at (100, 0) and a shelf taking books at (0, 100)
+    fn scene() -> Scene {
+        let mut card = Entity::new("card");
+        card.add_component(area(0., 0.));
+        card.add_component(Draggable {
+            payload: "card".to_string(),
+            ..Default::default()
+        });
+        let bin = drop_target("bin", 100., 0., "card");
+        let shelf = drop_target("shelf", 0., 100., "book");
+
+        let (card_id, bin_id, shelf_id) = (card.id(), bin.id(), shelf.id());
+        let mut root = Entity::new("root");
+        root.add_child(card);
+        root.add_child(bin);
+        root.add_child(shelf);
+
+        Scene {
+            root,
+            card: card_id,
+            bin: bin_id,
+            shelf: shelf_id,
+            resources: Resources::default(),
+            drag: Drag::default(),
+        }
+    }
+
+    impl Scene {
+        fn send(&mut self, ev: SFMLEvent) -> Vec<(EntityId, Event)> {
+            // Presses land on the card
+            let hit = match ev {
+                SFMLEvent::MouseButtonPressed { .. } => Some(PointerHit {
+                    id: self.card,
+                    local: (0., 0.),
+                }),
+                _ => None,
+            };
+            self.drag
+                .handle_event(&mut self.root, &self.resources, &ev, hit)
+        }
+
+        fn press(&mut self) -> Vec<(EntityId, Event)> {
+            self.send(SFMLEvent::MouseButtonPressed {
+                button: Button::Left,
+                x: 0,
+                y: 0,
+            })
+        }
+
+        fn move_to(&mut self, x: i32, y: i32) -> Vec<(EntityId, Event)> {
+            self.send(SFMLEvent::MouseMoved { x, y })
+        }
+
+        fn release(&mut self, x: i32, y: i32) -> Vec<(EntityId, Event)> {
+            self.send(SFMLEvent::MouseButtonReleased {
+                button: Button::Left,
+                x,
+                y,
+            })
+        }
+
+        fn card_position(&mut self) -> (f32, f32) {
+            let card = self.root.find_by_id(self.card).unwrap();
+            let transform = card.get_named::<Transform>(TRANSFORM_NAME).unwrap();
+            (transform.x, transform.y)
+        }
+    }
+
+    /// Kind of each drag event with the Entity it is delivered to
+    fn kinds(events: &[(EntityId, Event)]) -> Vec<(&'static str, EntityId)> {
+        events
+            .iter()
+            .map(|(id, ev)| {
+                let kind = match ev {
+                    Event::DragStart(_) => "start",
+                    Event::DragMove(_) => "move",
+                    Event::DragEnd(_) => "end",
+                    Event::DragEnter(_) => "enter",
+                    Event::DragLeave(_) => "leave",
+                    Event::Drop(_) => "drop",
+                    _ => "other",
+                };
+                (kind, *id)
+            })
+            .collect()
+    }
+
+    fn drag_payload(events: &[(EntityId, Event)], index: usize) -> DragPayload {
+        match &events[index] {
+            (_, Event::DragStart(p) | Event::DragMove(p) | Event::DragEnd(p)) => *p,
+            other => panic!("expected a drag event, got {:?}", other),
+        }
+    }
+
+    fn drop_payload(events: &[(EntityId, Event)], index: usize) -> DropPayload {
+        match &events[index] {
+            (_, Event::DragEnter(p) | Event::DragLeave(p) | Event::Drop(p)) => p.clone(),
+            other => panic!("expected a drop target event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn press_turns_into_a_drag_past_the_threshold() {
+        let mut s = scene();
+        assert!(s.press().is_empty());
+        assert!(s.move_to(2, 2).is_empty());
+        assert_eq!(s.drag.dragging(), None);
+
+        let events = s.move_to(10, 0);
+        assert_eq!(kinds(&events), [("start", s.card), ("move", s.card)]);
+        assert_eq!(s.drag.dragging(), Some(s.card));
+        assert_eq!(s.card_position(), (10., 0.));
+
+        let moved = drag_payload(&events, 1);
+        assert_eq!((moved.x, moved.y, moved.position), (10., 0., (10., 0.)));
+        assert_eq!(moved.target, None);
+    }
+
+    #[test]
+    fn release_before_the_threshold_is_not_a_drag() {
+        let mut s = scene();
+        s.press();
+        s.move_to(1, 1);
+        assert!(s.release(1, 1).is_empty());
+        assert_eq!(s.card_position(), (0., 0.));
+    }
+
+    #[test]
+    fn accepting_target_gets_enter_and_drop() {
+        let mut s = scene();
+        s.press();
+        let events = s.move_to(100, 0);
+        assert_eq!(
+            kinds(&events),
+            [("start", s.card), ("enter", s.bin), ("move", s.card)]
+        );
+        let entered = drop_payload(&events, 1);
+        assert_eq!((entered.source, entered.target), (s.card, s.bin));
+        assert_eq!((entered.payload.as_str(), entered.accepted), ("card", true));
+
+        let events = s.release(100, 0);
+        assert_eq!(kinds(&events), [("drop", s.bin), ("end", s.card)]);
+        assert!(drop_payload(&events, 0).accepted);
+        let ended = drag_payload(&events, 1);
+        assert_eq!((ended.target, ended.accepted), (Some(s.bin), true));
+        assert_eq!(s.drag.dragging(), None);
+    }
+
+    #[test]
+    fn rejecting_target_gets_enter_and_leave() {
+        let mut s = scene();
+        s.press();
+        let events = s.move_to(0, 100);
+        assert_eq!(
+            kinds(&events),
+            [("start", s.card), ("enter", s.shelf), ("move", s.card)]
+        );
+        assert!(!drop_payload(&events, 1).accepted);
+
+        let events = s.release(0, 100);
+        assert_eq!(kinds(&events), [("leave", s.shelf), ("end", s.card)]);
+        let ended = drag_payload(&events, 1);
+        assert_eq!((ended.target, ended.accepted), (Some(s.shelf), false));
+    }
+
+    #[test]
+    fn moving_off_a_target_leaves_it() {
+        let mut s = scene();
+        s.press();
+        s.move_to(100, 0);
+
+        let events = s.move_to(100, 100);
+        assert_eq!(kinds(&events), [("leave", s.bin), ("move", s.card)]);
+        assert_eq!(drag_payload(&events, 1).target, None);
+    }
+
+    #[test]
+    fn losing_focus_cancels_the_drop() {
+        let mut s = scene();
+        s.press();
+        s.move_to(100, 0);
+
+        let events = s.send(SFMLEvent::LostFocus);
+        assert_eq!(kinds(&events), [("leave", s.bin), ("end", s.card)]);
+        let ended = drag_payload(&events, 1);
+        assert_eq!((ended.target, ended.accepted), (None, false));
+        assert_eq!(s.drag.dragging(), None);
+    }
+}
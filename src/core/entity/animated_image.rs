@@ -1,8 +1,8 @@
 use crate::app::App;
 use crate::core::component::image::{Image, ImageRenderType};
-use crate::core::component::Component;
-use crate::core::entity::{Entity, EntityFns};
-use crate::core::event::{Event, UpdateRenderablePayload};
+use crate::core::entity::behavior::EntityBehavior;
+use crate::core::entity::Entity;
+use crate::core::event::{Event, EventContext, UpdateRenderablePayload};
 
 use crate::util::rect::Rect;
 
@@ -11,108 +11,139 @@ extern crate nalgebra_glm as glm;
 use core::any::Any;
 use std::collections::HashMap;
 
-static ANIMATED_IMAGE_TEXTURE_NAME: &str = "__AI_TEXTURE__";
+pub static ANIMATED_IMAGE_TEXTURE_NAME: &str = "__AI_TEXTURE__";
 
 #[derive(Default, Debug, Clone)]
-struct Data {
+pub struct AnimatedImage {
     timer: u64,
     fps_mult: f32,
     frame: usize,
     frames: Vec<Rect>,
     frame_range: (usize, usize),
+    animation: Option<String>,
     animations: HashMap<String, (usize, usize)>,
 }
 
-impl Component for Data {
-    fn get_name(&self) -> &str {
-        "data"
+impl AnimatedImage {
+    pub fn set_animation(&mut self, name: &str) {
+        if self.animations.contains_key(name) {
+            self.frame_range = self.animations[name];
+            self.frame = 0;
+            self.animation = Some(name.to_string());
+        }
     }
 
-    fn as_any(&mut self) -> &mut dyn Any {
-        self
+    /// Name of the active animation, None while playing every frame
+    pub fn animation(&self) -> Option<&str> {
+        self.animation.as_deref()
+    }
+
+    pub fn animations(&self) -> &HashMap<String, (usize, usize)> {
+        &self.animations
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps_mult
+    }
+
+    /// Width/Height of a single cell of the sprite sheet
+    pub fn cell_size(&self) -> (f32, f32) {
+        match self.frames.first() {
+            Some(frame) => (frame.w, frame.h),
+            None => (0., 0.),
+        }
+    }
+
+    pub fn add_animation(&mut self, name: &str, frame_range: (usize, usize)) {
+        self.animations.insert(name.to_string(), frame_range);
     }
 }
 
-fn update_animated_image(e: &mut Entity, _app: &App, dt: f32) {
-    let data;
-    {
-        let d = e.find_component::<Data>("data").unwrap();
-        let range = d.frame_range.1 - d.frame_range.0;
-        d.timer += (dt * 1000. * d.fps_mult) as u64;
+impl EntityBehavior for AnimatedImage {
+    fn update(&mut self, e: &mut Entity, _app: &App, dt: f32) {
+        let range = self.frame_range.1 - self.frame_range.0;
+        self.timer += (dt * 1000. * self.fps_mult) as u64;
         let pos = match range {
             0 => 0,
-            _ => (d.timer / 1000) as usize % range,
+            _ => (self.timer / 1000) as usize % range,
         };
-        d.frame = d.frame_range.0 + pos;
-        data = d.clone();
-    }
+        self.frame = self.frame_range.0 + pos;
 
-    {
         let img = e
             .find_component::<Image>(ANIMATED_IMAGE_TEXTURE_NAME)
             .unwrap();
-        img.r_rect = Some(data.frames[data.frame]);
+        img.r_rect = Some(self.frames[self.frame]);
     }
-}
 
-#[allow(clippy::single_match)]
-fn handle_event(e: &mut Entity, _app: &mut Option<&mut App>, ev: &Event) {
-    match ev {
-        Event::UpdateRenderable(p) => {
-            let img = e
-                .find_component::<Image>(ANIMATED_IMAGE_TEXTURE_NAME)
-                .unwrap();
-
-            match p {
-                UpdateRenderablePayload::X(x) => {
-                    img.x = *x;
-                }
-                UpdateRenderablePayload::MoveX(mx) => {
-                    img.x += *mx;
-                }
-                UpdateRenderablePayload::Y(y) => {
-                    img.y = *y;
-                }
-                UpdateRenderablePayload::MoveY(my) => {
-                    img.y += *my;
-                }
-                UpdateRenderablePayload::Width(w) => {
-                    img.width = *w;
-                }
-                UpdateRenderablePayload::Height(h) => {
-                    img.height = *h;
-                }
-                UpdateRenderablePayload::Rotation(r) => {
-                    img.rotation = *r;
-                }
-                UpdateRenderablePayload::ScaleX(sx) => {
-                    img.scale.x = *sx;
-                }
-                UpdateRenderablePayload::ScaleY(sy) => {
-                    img.scale.y = *sy;
-                }
-                UpdateRenderablePayload::Alpha(a) => {
-                    img.alpha.val = *a;
-                }
-                UpdateRenderablePayload::ColorMod(r, g, b) => {
-                    img.color.update(*r, *g, *b);
+    #[allow(clippy::single_match)]
+    fn event(
+        &mut self,
+        e: &mut Entity,
+        _app: &mut Option<&mut App>,
+        ev: &Event,
+        _ctx: &mut EventContext,
+    ) {
+        match ev {
+            Event::UpdateRenderable(p) => {
+                let img = e
+                    .find_component::<Image>(ANIMATED_IMAGE_TEXTURE_NAME)
+                    .unwrap();
+
+                match p {
+                    UpdateRenderablePayload::X(x) => {
+                        img.x = *x;
+                    }
+                    UpdateRenderablePayload::MoveX(mx) => {
+                        img.x += *mx;
+                    }
+                    UpdateRenderablePayload::Y(y) => {
+                        img.y = *y;
+                    }
+                    UpdateRenderablePayload::MoveY(my) => {
+                        img.y += *my;
+                    }
+                    UpdateRenderablePayload::Width(w) => {
+                        img.width = *w;
+                    }
+                    UpdateRenderablePayload::Height(h) => {
+                        img.height = *h;
+                    }
+                    UpdateRenderablePayload::Rotation(r) => {
+                        img.rotation = *r;
+                    }
+                    UpdateRenderablePayload::ScaleX(sx) => {
+                        img.scale.x = *sx;
+                    }
+                    UpdateRenderablePayload::ScaleY(sy) => {
+                        img.scale.y = *sy;
+                    }
+                    UpdateRenderablePayload::Alpha(a) => {
+                        img.alpha.val = *a;
+                    }
+                    UpdateRenderablePayload::ColorMod(r, g, b) => {
+                        img.color.update(*r, *g, *b);
+                    }
                 }
             }
+            _ => {}
         }
-        _ => {}
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
     }
 }
 
 pub fn animated_image_set_animation(e: &mut Entity, name: &str) {
-    let d = e.find_component::<Data>("data").unwrap();
-    if d.animations.contains_key(name) {
-        d.frame_range = d.animations[name];
-        d.frame = 0;
-    }
+    e.behavior_mut::<AnimatedImage>()
+        .unwrap()
+        .set_animation(name);
 }
+
 pub fn animated_image_add_animation(e: &mut Entity, name: &str, frame_range: (usize, usize)) {
-    let d = e.find_component::<Data>("data").unwrap();
-    d.animations.insert(name.to_string(), frame_range);
+    e.behavior_mut::<AnimatedImage>()
+        .unwrap()
+        .add_animation(name, frame_range);
 }
 
 pub fn animated_image_get_position(e: &mut Entity) -> (f32, f32) {
@@ -134,23 +165,15 @@ pub fn make_animated_image(
     fps: Option<f32>,
     image_render_type: Option<ImageRenderType>,
 ) -> Entity {
-    let mut e = Entity::new(
-        name,
-        EntityFns {
-            update_fn: update_animated_image,
-            event_fn: handle_event,
-            ..Default::default()
-        },
-    );
-
     let texture_info = app.resource.load_image_from_disk(texture_name).unwrap();
 
     // Create frames
     let mut x_pos = 0;
     let mut y_pos = 0;
 
+    let mut e;
     {
-        let mut d = Data::default();
+        let mut d = AnimatedImage::default();
         while (y_pos + height as u32) <= texture_info.height {
             while (x_pos + width as u32) <= texture_info.width {
                 d.frames.push(Rect {
@@ -172,7 +195,7 @@ pub fn make_animated_image(
             _ => 1.,
         };
 
-        e.add_component(d);
+        e = Entity::with_behavior(name, d);
     }
 
     {
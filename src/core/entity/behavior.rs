@@ -0,0 +1,241 @@
+use crate::app::App;
+use crate::core::entity::Entity;
+use crate::core::event::{Event, EventContext};
+use crate::core::scene::SceneBehavior;
+
+use core::any::Any;
+use serde_json::Value;
+
+extern crate nalgebra_glm as glm;
+
+/// Per Entity logic. Implementors own their state and receive the Entity they are attached to
+/// in each hook. While a hook runs the behavior is detached from its Entity.
+pub trait EntityBehavior {
+    /// Called once per frame with the deltatime from the previous frame
+    fn update(&mut self, _e: &mut Entity, _app: &App, _dt: f32) {}
+
+    /// Event responder called on the way down the tree, before any children see the event
+    fn capture_event(
+        &mut self,
+        _e: &mut Entity,
+        _app: &mut Option<&mut App>,
+        _ev: &Event,
+        _ctx: &mut EventContext,
+    ) {
+    }
+
+    /// Event responder called on the way back up the tree, after all children have seen the event
+    fn event(
+        &mut self,
+        _e: &mut Entity,
+        _app: &mut Option<&mut App>,
+        _ev: &Event,
+        _ctx: &mut EventContext,
+    ) {
+    }
+
+    /// Called right before the Entity's components are rendered with the Entity's world matrix
+    fn prerender(&mut self, _e: &mut Entity, _world: &glm::TMat4<f32>) {}
+
+    /// Called when the Entity is added as a child of another Entity
+    fn on_attach(&mut self, _e: &mut Entity) {}
+
+    /// Called when the Entity is removed from its parent
+    fn on_detach(&mut self, _e: &mut Entity) {}
+
+    /// Serialized form of the behavior used when saving scenes. Behaviors returning None are not saved.
+    fn describe(&self) -> Option<SceneBehavior> {
+        None
+    }
+
+    /// State compared when a replay ends, see `tree_checksum`. Defaults to the parameters of
+    /// `describe`, behaviors with state which scenes do not save should return all of it.
+    fn checksum_state(&self) -> Option<Value> {
+        self.describe().map(|desc| Value::Object(desc.params))
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any;
+}
+
+type UpdateFn = Box<dyn FnMut(&mut Entity, &App, f32)>;
+type EventFn = Box<dyn FnMut(&mut Entity, &mut Option<&mut App>, &Event, &mut EventContext)>;
+type PrerenderFn = Box<dyn FnMut(&mut Entity, &glm::TMat4<f32>)>;
+type LifecycleFn = Box<dyn FnMut(&mut Entity)>;
+
+/// EntityBehavior built from closures for Entities which do not need a dedicated type.
+#[derive(Default)]
+pub struct ClosureBehavior {
+    update: Option<UpdateFn>,
+    capture_event: Option<EventFn>,
+    event: Option<EventFn>,
+    prerender: Option<PrerenderFn>,
+    attach: Option<LifecycleFn>,
+    detach: Option<LifecycleFn>,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl ClosureBehavior {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_update(mut self, f: impl FnMut(&mut Entity, &App, f32) + 'static) -> Self {
+        self.update = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_capture_event(
+        mut self,
+        f: impl FnMut(&mut Entity, &mut Option<&mut App>, &Event, &mut EventContext) + 'static,
+    ) -> Self {
+        self.capture_event = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_event(
+        mut self,
+        f: impl FnMut(&mut Entity, &mut Option<&mut App>, &Event, &mut EventContext) + 'static,
+    ) -> Self {
+        self.event = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_prerender(mut self, f: impl FnMut(&mut Entity, &glm::TMat4<f32>) + 'static) -> Self {
+        self.prerender = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_attach(mut self, f: impl FnMut(&mut Entity) + 'static) -> Self {
+        self.attach = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_detach(mut self, f: impl FnMut(&mut Entity) + 'static) -> Self {
+        self.detach = Some(Box::new(f));
+        self
+    }
+}
+
+impl EntityBehavior for ClosureBehavior {
+    fn update(&mut self, e: &mut Entity, app: &App, dt: f32) {
+        if let Some(f) = &mut self.update {
+            f(e, app, dt);
+        }
+    }
+
+    fn capture_event(
+        &mut self,
+        e: &mut Entity,
+        app: &mut Option<&mut App>,
+        ev: &Event,
+        ctx: &mut EventContext,
+    ) {
+        if let Some(f) = &mut self.capture_event {
+            f(e, app, ev, ctx);
+        }
+    }
+
+    fn event(
+        &mut self,
+        e: &mut Entity,
+        app: &mut Option<&mut App>,
+        ev: &Event,
+        ctx: &mut EventContext,
+    ) {
+        if let Some(f) = &mut self.event {
+            f(e, app, ev, ctx);
+        }
+    }
+
+    fn prerender(&mut self, e: &mut Entity, world: &glm::TMat4<f32>) {
+        if let Some(f) = &mut self.prerender {
+            f(e, world);
+        }
+    }
+
+    fn on_attach(&mut self, e: &mut Entity) {
+        if let Some(f) = &mut self.attach {
+            f(e);
+        }
+    }
+
+    fn on_detach(&mut self, e: &mut Entity) {
+        if let Some(f) = &mut self.detach {
+            f(e);
+        }
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::EventPhase;
+
+    use sfml::window::Event as SFMLEvent;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type HookLog = Rc<RefCell<Vec<String>>>;
+
+    /// Behavior logging every hook as `<hook>:<entity name>`
+    fn logged(log: &HookLog) -> ClosureBehavior {
+        let entry = |hook: &'static str| {
+            let log = log.clone();
+            move |e: &mut Entity| log.borrow_mut().push(format!("{}:{}", hook, e.name))
+        };
+        let update = entry("update");
+        let capture = entry("capture");
+        let event = entry("event");
+        let prerender = entry("prerender");
+        ClosureBehavior::new()
+            .on_update(move |e, _, dt| {
+                assert_eq!(dt, 0.5);
+                update(e)
+            })
+            .on_capture_event(move |e, _, _, ctx| {
+                assert_eq!(ctx.phase, EventPhase::Capture);
+                capture(e)
+            })
+            .on_event(move |e, _, _, ctx| {
+                assert_eq!(ctx.phase, EventPhase::Bubble);
+                event(e)
+            })
+            .on_prerender(move |e, world| {
+                assert_eq!(*world, glm::identity::<f32, 4>());
+                prerender(e)
+            })
+            .on_attach(entry("attach"))
+            .on_detach(entry("detach"))
+    }
+
+    #[test]
+    fn each_closure_runs_when_its_hook_fires() {
+        let log = HookLog::default();
+        let child = Entity::with_behavior("child", logged(&log));
+        let id = child.id();
+        let mut root = Entity::new("root");
+
+        root.add_child(child);
+        let app = App::default();
+        root.update(&app, 0.5);
+        root.handle_event(&mut None, &Event::SFMLEvent(SFMLEvent::LostFocus));
+        root.render_components(&app, &glm::identity::<f32, 4>());
+        root.despawn_child(id);
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "attach:child",
+                "update:child",
+                "capture:child",
+                "event:child",
+                "prerender:child",
+                "detach:child",
+            ]
+        );
+    }
+}
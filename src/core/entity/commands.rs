@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+
+use crate::core::component::Component;
+use crate::core::entity::{Entity, EntityId};
+
+/// Structural change to the Entity tree which is deferred until the tree is no longer being traversed.
+pub enum EntityCommand {
+    Spawn {
+        parent: EntityId,
+        entity: Entity,
+    },
+    Despawn(EntityId),
+    Reparent {
+        entity: EntityId,
+        parent: EntityId,
+    },
+    AddComponent {
+        entity: EntityId,
+        component: Box<dyn Component>,
+    },
+    RemoveComponent {
+        entity: EntityId,
+        name: String,
+    },
+}
+
+/// Queue of EntityCommands filled during update/event handling and flushed by App::run once
+/// per frame, after the update pass and before the tree is reordered and rendered.
+#[derive(Default)]
+pub struct EntityCommands {
+    queue: RefCell<Vec<EntityCommand>>,
+}
+
+impl EntityCommands {
+    /// Queues `entity` to be added as a child of `parent`. Returns the id the Entity will have once spawned.
+    pub fn spawn(&self, parent: EntityId, entity: Entity) -> EntityId {
+        let id = entity.id();
+        self.queue
+            .borrow_mut()
+            .push(EntityCommand::Spawn { parent, entity });
+        id
+    }
+
+    pub fn despawn(&self, entity: EntityId) {
+        self.queue.borrow_mut().push(EntityCommand::Despawn(entity));
+    }
+
+    pub fn reparent(&self, entity: EntityId, parent: EntityId) {
+        self.queue
+            .borrow_mut()
+            .push(EntityCommand::Reparent { entity, parent });
+    }
+
+    pub fn add_component<T: Component + 'static>(&self, entity: EntityId, cmp: T) {
+        self.queue.borrow_mut().push(EntityCommand::AddComponent {
+            entity,
+            component: Box::new(cmp),
+        });
+    }
+
+    /// Queues removal of every Component named `name` on the target Entity.
+    pub fn remove_component(&self, entity: EntityId, name: &str) {
+        self.queue
+            .borrow_mut()
+            .push(EntityCommand::RemoveComponent {
+                entity,
+                name: name.to_string(),
+            });
+    }
+
+    /// Applies all queued commands to the tree in the order they were issued. Commands targeting
+    /// Entities which no longer exist are dropped.
+    pub fn flush(&self, root: &mut Entity) {
+        let commands = self.queue.take();
+        for cmd in commands {
+            if let Err(msg) = apply_command(root, cmd) {
+                println!("Failed to apply entity command: {}", msg);
+            }
+        }
+    }
+}
+
+fn apply_command(root: &mut Entity, cmd: EntityCommand) -> Result<(), String> {
+    match cmd {
+        EntityCommand::Spawn { parent, entity } => {
+            let parent = root.find_by_id(parent).map_err(|e| format!("{:?}", e))?;
+            parent.add_child(entity);
+        }
+        EntityCommand::Despawn(id) => {
+            if !root.despawn_child(id) {
+                return Err(format!("Could not despawn entity: {:?}", id));
+            }
+        }
+        EntityCommand::Reparent { entity, parent } => {
+            {
+                let ent = root.find_by_id(entity).map_err(|e| format!("{:?}", e))?;
+                if ent.find_by_id(parent).is_ok() {
+                    return Err(format!(
+                        "Cannot reparent entity {:?} into its own subtree",
+                        entity
+                    ));
+                }
+            }
+            root.find_by_id(parent).map_err(|e| format!("{:?}", e))?;
+
+            let ent = root
+                .take_child(entity)
+                .ok_or(format!("Could not reparent entity: {:?}", entity))?;
+            root.find_by_id(parent)
+                .map_err(|e| format!("{:?}", e))?
+                .add_child(ent);
+        }
+        EntityCommand::AddComponent { entity, component } => {
+            root.find_by_id(entity)
+                .map_err(|e| format!("{:?}", e))?
+                .add_boxed_component(component);
+        }
+        EntityCommand::RemoveComponent { entity, name } => {
+            root.find_by_id(entity)
+                .map_err(|e| format!("{:?}", e))?
+                .remove_components_by_name(&name);
+        }
+    }
+
+    Ok(())
+}
@@ -1,31 +1,52 @@
 pub mod animated_image;
+pub mod behavior;
+pub mod commands;
+pub mod pick;
+pub mod query;
+
+use std::any::TypeId;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::app::App;
-use crate::core::component::offset::{Offset, OFFSET_NAME};
+use crate::core::component::render_target::{RenderTarget, RENDER_TARGET_NAME};
+use crate::core::component::transform::{Transform, TRANSFORM_NAME};
 use crate::core::component::Component;
-use crate::core::event::{Event, UpdateRenderablePayload};
+use crate::core::entity::behavior::EntityBehavior;
+use crate::core::event::{Event, EventContext, EventPhase, UpdateRenderablePayload};
+use crate::core::scene::SceneBehavior;
 
-pub struct EntityFns {
-    /// Called once per frame with the deltatime from the previous frame
-    pub update_fn: fn(&mut Entity, &App, f32),
+use serde_json::Value;
 
-    /// Event responder function
-    pub event_fn: fn(&mut Entity, &mut Option<&mut App>, &Event),
+extern crate nalgebra_glm as glm;
 
-    /// Called right before the Entitie's components are rendered
-    pub prerender_fn: fn(&mut Entity, parent_offset: (f32, f32)),
-}
+/// Stable identifier assigned to every Entity on construction. Ids are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId(u64);
 
-impl Default for EntityFns {
-    fn default() -> Self {
-        EntityFns {
-            update_fn: |_e, _a, _d| {},
-            event_fn: |_e, _a, _ev| {},
-            prerender_fn: |_e, _o| {},
-        }
+static NEXT_ENTITY_ID: AtomicU64 = AtomicU64::new(1);
+
+impl EntityId {
+    fn next() -> Self {
+        EntityId(NEXT_ENTITY_ID.fetch_add(1, Ordering::Relaxed))
     }
 }
 
+/// Bumped whenever an Entity is added to or taken out of any tree, see `EntityIndex::is_stale`
+static TREE_VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn tree_changed() {
+    TREE_VERSION.fetch_add(1, Ordering::Relaxed);
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityLookupError {
+    ChildNotFound(String),
+    IdNotFound(EntityId),
+    PathNotFound(String),
+}
+
 /// Entities are composed of a combination of Components, and other Entities.
 enum EntityChild {
     Component(Box<dyn Component>),
@@ -33,71 +54,325 @@ enum EntityChild {
 }
 
 pub struct Entity {
+    id: EntityId,
     pub name: String,
     pub zindex: i32,
     pub active: bool,
     pub visible: bool,
+    tags: BTreeSet<String>,
     children: Vec<EntityChild>,
-    vtable: EntityFns,
+    /// Positions in `children` of the components of each type
+    component_index: HashMap<TypeId, Vec<usize>>,
+    behavior: Option<Box<dyn EntityBehavior>>,
+    world: glm::TMat4<f32>,
 }
 
 impl Default for Entity {
     fn default() -> Self {
         Self {
+            id: EntityId::next(),
             name: "".to_string(),
             zindex: 0,
             active: true,
             visible: true,
+            tags: BTreeSet::new(),
             children: Vec::default(),
-            vtable: EntityFns::default(),
+            component_index: HashMap::new(),
+            behavior: None,
+            world: glm::identity::<f32, 4>(),
         }
     }
 }
 
 impl Entity {
-    pub fn new(name: &str, vtable: EntityFns) -> Self {
+    pub fn new(name: &str) -> Self {
         Entity {
             name: name.to_string(),
-            zindex: 0,
-            active: true,
-            visible: true,
-            children: Vec::default(),
-            vtable,
+            ..Default::default()
         }
     }
 
+    pub fn with_behavior<T: EntityBehavior + 'static>(name: &str, behavior: T) -> Self {
+        Entity {
+            name: name.to_string(),
+            behavior: Some(Box::new(behavior)),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_boxed_behavior(&mut self, behavior: Box<dyn EntityBehavior>) {
+        self.behavior = Some(behavior);
+    }
+
+    pub fn describe_behavior(&self) -> Option<SceneBehavior> {
+        self.behavior.as_ref()?.describe()
+    }
+
+    pub fn behavior_checksum_state(&self) -> Option<Value> {
+        self.behavior.as_ref()?.checksum_state()
+    }
+
+    /// Local to screen space matrix from the most recent render, including this Entity's own Transform
+    pub fn world_transform(&self) -> &glm::TMat4<f32> {
+        &self.world
+    }
+
+    pub fn set_behavior<T: EntityBehavior + 'static>(&mut self, behavior: T) {
+        self.behavior = Some(Box::new(behavior));
+    }
+
+    /// Typed access to the Entity's behavior. Returns None while the behavior's own hooks are running.
+    pub fn behavior_mut<T: EntityBehavior + 'static>(&mut self) -> Option<&mut T> {
+        self.behavior.as_mut()?.as_any().downcast_mut()
+    }
+
+    /// Detaches the behavior for the duration of `f` so it can borrow the Entity mutably.
+    fn with_behavior_hook(&mut self, f: impl FnOnce(&mut dyn EntityBehavior, &mut Entity)) {
+        if let Some(mut behavior) = self.behavior.take() {
+            f(behavior.as_mut(), self);
+            if self.behavior.is_none() {
+                self.behavior = Some(behavior);
+            }
+        }
+    }
+
+    /// Delivers an event to this Entity only, without propagating it to children.
+    fn dispatch_event(&mut self, a: &mut Option<&mut App>, e: &Event) {
+        let mut ctx = EventContext::new(EventPhase::Bubble);
+        self.bubble_event(a, e, &mut ctx);
+    }
+
+    /// Bubble phase for this Entity alone: components first, then the behavior.
+    fn bubble_event(&mut self, a: &mut Option<&mut App>, e: &Event, ctx: &mut EventContext) {
+        for cmp in self.components_mut() {
+            if ctx.is_handled() {
+                return;
+            }
+            cmp.on_event(a, e, ctx);
+        }
+
+        if !ctx.is_handled() {
+            self.with_behavior_hook(|b, ent| b.event(ent, a, e, ctx));
+        }
+    }
+
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        self.tags.insert(tag.to_string());
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &String> {
+        self.tags.iter()
+    }
+
     pub fn add_component<T: Component + 'static>(&mut self, cmp: T) {
-        self.children.push(EntityChild::Component(Box::new(cmp)));
+        self.add_boxed_component(Box::new(cmp));
+    }
+
+    pub fn add_boxed_component(&mut self, mut cmp: Box<dyn Component>) {
+        cmp.on_attach(self);
+        self.component_index
+            .entry(cmp.as_any_ref().type_id())
+            .or_default()
+            .push(self.children.len());
+        self.children.push(EntityChild::Component(cmp));
+    }
+
+    /// Rebuilds the type index after `children` has been reordered or had items removed.
+    fn reindex_components(&mut self) {
+        self.component_index.clear();
+        for (i, c) in self.children.iter().enumerate() {
+            if let EntityChild::Component(cmp) = c {
+                self.component_index
+                    .entry(cmp.as_any_ref().type_id())
+                    .or_default()
+                    .push(i);
+            }
+        }
+    }
+
+    fn component_positions<T: Component + 'static>(&self) -> &[usize] {
+        match self.component_index.get(&TypeId::of::<T>()) {
+            Some(positions) => positions,
+            None => &[],
+        }
     }
 
-    pub fn add_child(&mut self, ent: Entity) {
+    fn component_at<T: Component + 'static>(&self, position: usize) -> Option<&T> {
+        match &self.children[position] {
+            EntityChild::Component(cmp) => cmp.as_any_ref().downcast_ref(),
+            _ => None,
+        }
+    }
+
+    fn component_at_mut<T: Component + 'static>(&mut self, position: usize) -> Option<&mut T> {
+        match &mut self.children[position] {
+            EntityChild::Component(cmp) => cmp.as_any().downcast_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn has<T: Component + 'static>(&self) -> bool {
+        !self.component_positions::<T>().is_empty()
+    }
+
+    /// The first component of type T, in z-order.
+    pub fn get<T: Component + 'static>(&self) -> Option<&T> {
+        let position = *self.component_positions::<T>().first()?;
+        self.component_at(position)
+    }
+
+    pub fn get_mut<T: Component + 'static>(&mut self) -> Option<&mut T> {
+        let position = *self.component_positions::<T>().first()?;
+        self.component_at_mut(position)
+    }
+
+    /// Every component of type T, in z-order.
+    pub fn get_all<T: Component + 'static>(&self) -> impl Iterator<Item = &T> {
+        self.component_positions::<T>()
+            .iter()
+            .filter_map(move |p| self.component_at(*p))
+    }
+
+    pub fn get_all_mut<T: Component + 'static>(&mut self) -> impl Iterator<Item = &mut T> {
+        let positions = self.component_positions::<T>().to_vec();
+        self.children
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| positions.binary_search(i).is_ok())
+            .filter_map(|(_, c)| match c {
+                EntityChild::Component(cmp) => cmp.as_any().downcast_mut(),
+                _ => None,
+            })
+    }
+
+    /// The first component of type T with the provided name.
+    pub fn get_named<T: Component + 'static>(&self, name: &str) -> Option<&T> {
+        let position = self.named_position::<T>(name)?;
+        self.component_at(position)
+    }
+
+    pub fn get_named_mut<T: Component + 'static>(&mut self, name: &str) -> Option<&mut T> {
+        let position = self.named_position::<T>(name)?;
+        self.component_at_mut(position)
+    }
+
+    fn named_position<T: Component + 'static>(&self, name: &str) -> Option<usize> {
+        self.component_positions::<T>()
+            .iter()
+            .copied()
+            .find(|p| match &self.children[*p] {
+                EntityChild::Component(cmp) => cmp.get_name() == name,
+                _ => false,
+            })
+    }
+
+    pub fn add_child(&mut self, mut ent: Entity) {
+        ent.with_behavior_hook(|b, e| b.on_attach(e));
         self.children.push(EntityChild::Entity(ent));
+        tree_changed();
     }
 
     pub fn find_component<T: Component + 'static>(&mut self, name: &str) -> Result<&mut T, String> {
-        match self.find_component_by_name(name)?.as_any().downcast_mut() {
-            Some(typed_cmp) => Ok(typed_cmp),
-            None => Err(format!("Could not find component with name: {}", name)),
-        }
+        self.get_named_mut(name)
+            .ok_or_else(|| format!("Could not find component with name: {}", name))
     }
 
-    pub fn find_component_by_name(
-        &mut self,
-        name: &str,
-    ) -> Result<&mut Box<dyn Component>, String> {
+    pub fn components_mut(&mut self) -> impl Iterator<Item = &mut (dyn Component + 'static)> {
+        self.children.iter_mut().filter_map(|c| match c {
+            EntityChild::Component(cmp) => Some(cmp.as_mut()),
+            _ => None,
+        })
+    }
+
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.children.iter_mut().filter_map(|c| match c {
+            EntityChild::Entity(ent) => Some(ent),
+            _ => None,
+        })
+    }
+
+    /// Removes the descendant Entity with the provided id from the tree and returns it.
+    pub fn take_child(&mut self, id: EntityId) -> Option<Entity> {
+        let position = self.children.iter().position(|c| match c {
+            EntityChild::Entity(ent) => ent.id == id,
+            _ => false,
+        });
+
+        if let Some(position) = position {
+            if let EntityChild::Entity(mut ent) = self.children.remove(position) {
+                self.reindex_components();
+                tree_changed();
+                ent.with_behavior_hook(|b, e| b.on_detach(e));
+                return Some(ent);
+            }
+        }
+
         for c in &mut self.children {
-            if let EntityChild::Component(cmp) = c {
-                let component = &*cmp;
-                if component.get_name() == name {
-                    return Ok(cmp);
+            if let EntityChild::Entity(ent) = c {
+                if let Some(found) = ent.take_child(id) {
+                    return Some(found);
                 }
             }
         }
 
-        Err(format!("Could not find component with name: {}", name))
+        None
+    }
+
+    /// Removes the descendant Entity with the provided id and detaches its whole subtree, for
+    /// Entities which are destroyed rather than moved. Returns whether it was found.
+    pub fn despawn_child(&mut self, id: EntityId) -> bool {
+        match self.take_child(id) {
+            Some(mut ent) => {
+                ent.detach_subtree();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Calls `on_detach` on the behavior of every descendant and on the Components of every
+    /// Entity in the subtree. A behavior is detached before the children and Components of its Entity.
+    fn detach_subtree(&mut self) {
+        self.component_index.clear();
+        for c in std::mem::take(&mut self.children) {
+            match c {
+                EntityChild::Entity(mut ent) => {
+                    ent.with_behavior_hook(|b, e| b.on_detach(e));
+                    ent.detach_subtree();
+                }
+                EntityChild::Component(mut cmp) => cmp.on_detach(self),
+            }
+        }
+    }
+
+    /// Removes every Component on this Entity with the provided name, returning how many were removed.
+    pub fn remove_components_by_name(&mut self, name: &str) -> usize {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.children)
+            .into_iter()
+            .partition(|c| matches!(c, EntityChild::Component(cmp) if cmp.get_name() == name));
+        self.children = kept;
+        self.reindex_components();
+
+        let count = removed.len();
+        for c in removed {
+            if let EntityChild::Component(mut cmp) = c {
+                cmp.on_detach(self);
+            }
+        }
+
+        count
     }
 
-    pub fn find_child_by_name(&mut self, name: &str) -> Result<&mut Entity, String> {
+    /// Finds a direct child of this Entity by name. The first matching child wins.
+    pub fn find_child_by_name(&mut self, name: &str) -> Result<&mut Entity, EntityLookupError> {
         for c in &mut self.children {
             if let EntityChild::Entity(ent) = c {
                 if ent.name == name {
@@ -106,19 +381,78 @@ impl Entity {
             }
         }
 
-        Err(format!("Could not find child with name: {}", name))
+        Err(EntityLookupError::ChildNotFound(name.to_string()))
     }
 
-    pub fn handle_event(&mut self, a: &mut Option<&mut App>, e: &Event) {
-        if self.active {
-            (self.vtable.event_fn)(self, a, e);
+    /// Finds this Entity or any descendant with the provided id.
+    pub fn find_by_id(&mut self, id: EntityId) -> Result<&mut Entity, EntityLookupError> {
+        if self.id == id {
+            return Ok(self);
+        }
+
+        for c in &mut self.children {
+            if let EntityChild::Entity(ent) = c {
+                if let Ok(found) = ent.find_by_id(id) {
+                    return Ok(found);
+                }
+            }
+        }
+
+        Err(EntityLookupError::IdNotFound(id))
+    }
+
+    /// Finds a descendant by a slash separated path of child names relative to this Entity,
+    /// eg: "entry/testbed/test_button" from the root Entity.
+    pub fn find_by_path(&mut self, path: &str) -> Result<&mut Entity, EntityLookupError> {
+        let mut current = self;
+        for segment in path.split('/').filter(|s| !s.is_empty()) {
+            current = current
+                .find_child_by_name(segment)
+                .map_err(|_| EntityLookupError::PathNotFound(path.to_string()))?;
+        }
+
+        Ok(current)
+    }
+
+    /// Delivers an event to this Entity and its subtree. Each Entity sees the event in the capture
+    /// phase before its children and in the bubble phase after them. Pointer events visit children
+    /// in reverse z-order. Propagation ends as soon as a handler calls `stop_propagation`, the
+    /// return value tells whether one did.
+    pub fn handle_event(&mut self, a: &mut Option<&mut App>, e: &Event) -> bool {
+        let mut ctx = EventContext::default();
+        self.propagate_event(a, e, &mut ctx);
+        ctx.is_handled()
+    }
+
+    fn propagate_event(&mut self, a: &mut Option<&mut App>, e: &Event, ctx: &mut EventContext) {
+        if !self.active || ctx.is_handled() {
+            return;
+        }
+
+        ctx.phase = EventPhase::Capture;
+        self.with_behavior_hook(|b, ent| b.capture_event(ent, a, e, ctx));
+
+        {
+            let children: Box<dyn Iterator<Item = &mut EntityChild>> = match e.is_pointer_event() {
+                true => Box::new(self.children.iter_mut().rev()),
+                false => Box::new(self.children.iter_mut()),
+            };
+
+            for c in children {
+                if ctx.is_handled() {
+                    return;
+                }
 
-            for c in &mut self.children {
                 if let EntityChild::Entity(ent) = c {
-                    ent.handle_event(a, e);
+                    ent.propagate_event(a, e, ctx);
                 }
             }
         }
+
+        if !ctx.is_handled() {
+            ctx.phase = EventPhase::Bubble;
+            self.bubble_event(a, e, ctx);
+        }
     }
 
     /// Reorder children based on z_index value. This will recursively reorder the entire Entity tree.
@@ -137,6 +471,7 @@ impl Entity {
 
             av.partial_cmp(&bv).unwrap()
         });
+        self.reindex_components();
 
         // Recursive call to reorder whole graph
         for c in &mut self.children {
@@ -148,11 +483,12 @@ impl Entity {
 
     pub fn update(&mut self, app: &App, dt: f32) {
         if self.active {
-            (self.vtable.update_fn)(self, app, dt);
+            self.with_behavior_hook(|b, ent| b.update(ent, app, dt));
 
             for c in &mut self.children {
-                if let EntityChild::Entity(ent) = c {
-                    ent.update(app, dt);
+                match c {
+                    EntityChild::Entity(ent) => ent.update(app, dt),
+                    EntityChild::Component(cmp) => cmp.update(app, dt),
                 }
             }
         }
@@ -162,30 +498,109 @@ impl Entity {
         self.zindex
     }
 
-    pub fn render_components(&mut self, app: &App, parent_offset: (f32, f32)) {
+    pub fn render_components(&mut self, app: &App, parent: &glm::TMat4<f32>) {
         if self.visible {
-            let offset = match self.find_component::<Offset>(OFFSET_NAME) {
-                Ok(offset) => (parent_offset.0 + offset.x, parent_offset.1 + offset.y),
-                _ => parent_offset,
+            self.world = match self.get_named::<Transform>(TRANSFORM_NAME) {
+                Some(transform) => parent * transform.local_matrix(),
+                None => *parent,
             };
+            let world = self.world;
 
-            (self.vtable.prerender_fn)(self, offset);
+            self.with_behavior_hook(|b, ent| b.prerender(ent, &world));
 
-            for c in &mut self.children {
-                match c {
-                    EntityChild::Entity(ent) => {
-                        ent.render_components(app, offset);
+            let target = self.get_named_mut::<RenderTarget>(RENDER_TARGET_NAME);
+            match target.map(|t| t.begin(app)) {
+                // The subtree is drawn in the target's own space
+                Some(true) => {
+                    self.render_children(app, &glm::identity());
+                    if let Some(target) = self.get_named::<RenderTarget>(RENDER_TARGET_NAME) {
+                        target.end(app);
                     }
+                }
+                Some(false) => {}
+                None => self.render_children(app, &world),
+            }
+        }
+    }
 
-                    EntityChild::Component(cmp) => {
-                        cmp.render(app, offset);
-                    }
+    fn render_children(&mut self, app: &App, world: &glm::TMat4<f32>) {
+        for c in &mut self.children {
+            match c {
+                EntityChild::Entity(ent) => {
+                    ent.render_components(app, world);
+                }
+
+                EntityChild::Component(cmp) => {
+                    cmp.render(app, world);
                 }
             }
         }
     }
 }
 
+/// Snapshot of id <-> path mappings for an Entity tree. Paths are relative to the root Entity
+/// the index was built from and the first Entity for a given path wins.
+#[derive(Debug, Default)]
+pub struct EntityIndex {
+    paths: HashMap<EntityId, String>,
+    ids: HashMap<String, EntityId>,
+    /// `TREE_VERSION` when the index was built, None for an index never built
+    version: Option<u64>,
+}
+
+impl EntityIndex {
+    pub fn build(root: &Entity) -> Self {
+        let mut index = EntityIndex {
+            version: Some(TREE_VERSION.load(Ordering::Relaxed)),
+            ..Default::default()
+        };
+        index.insert(root, "".to_string());
+        index
+    }
+
+    /// Whether an Entity was added or removed anywhere since the index was built. Renaming an
+    /// Entity already in the tree does not count.
+    pub fn is_stale(&self) -> bool {
+        self.version != Some(TREE_VERSION.load(Ordering::Relaxed))
+    }
+
+    fn insert(&mut self, ent: &Entity, path: String) {
+        self.ids.entry(path.clone()).or_insert(ent.id);
+
+        for c in &ent.children {
+            if let EntityChild::Entity(child) = c {
+                let child_path = match path.is_empty() {
+                    true => child.name.clone(),
+                    false => format!("{}/{}", path, child.name),
+                };
+                self.insert(child, child_path);
+            }
+        }
+
+        self.paths.insert(ent.id, path);
+    }
+
+    pub fn id_of(&self, path: &str) -> Result<EntityId, EntityLookupError> {
+        let key = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        match self.ids.get(&key) {
+            Some(id) => Ok(*id),
+            None => Err(EntityLookupError::PathNotFound(path.to_string())),
+        }
+    }
+
+    pub fn path_of(&self, id: EntityId) -> Result<&str, EntityLookupError> {
+        match self.paths.get(&id) {
+            Some(path) => Ok(path),
+            None => Err(EntityLookupError::IdNotFound(id)),
+        }
+    }
+}
+
 pub trait RenderableEntity {
     fn set_x(&mut self, x: f32);
     fn move_x(&mut self, mx: f32);
@@ -203,90 +618,380 @@ pub trait RenderableEntity {
 // Functions to support "renderable" entities
 impl RenderableEntity for Entity {
     fn set_x(&mut self, x: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::X(x)),
         );
     }
 
     fn move_x(&mut self, mx: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::MoveX(mx)),
         );
     }
 
     fn set_y(&mut self, y: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::Y(y)),
         );
     }
 
     fn move_y(&mut self, my: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::MoveY(my)),
         );
     }
 
     fn set_width(&mut self, w: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::Width(w)),
         );
     }
 
     fn set_height(&mut self, h: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::Height(h)),
         );
     }
 
     fn set_rotation(&mut self, r: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::Rotation(r)),
         );
     }
 
     fn set_scale_x(&mut self, sx: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::ScaleX(sx)),
         );
     }
 
     fn set_scale_y(&mut self, sy: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::ScaleY(sy)),
         );
     }
 
     fn set_alpha(&mut self, a: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::Alpha(a)),
         );
     }
 
     fn set_color_mod(&mut self, r: f32, g: f32, b: f32) {
-        (self.vtable.event_fn)(
-            self,
+        self.dispatch_event(
             &mut None,
             &Event::UpdateRenderable(UpdateRenderablePayload::ColorMod(r, g, b)),
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::any::Any;
+    use sfml::window::Event as SFMLEvent;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type PhaseLog = Rc<RefCell<Vec<String>>>;
+
+    /// Logs both phases and stops propagation at `stop_at`, a name and phase
+    struct Tracer {
+        log: PhaseLog,
+        stop_at: Option<(&'static str, EventPhase)>,
+    }
+
+    impl Tracer {
+        fn visit(&self, e: &Entity, ctx: &mut EventContext) {
+            self.log
+                .borrow_mut()
+                .push(format!("{}:{:?}", e.name, ctx.phase));
+            if self.stop_at == Some((e.name.as_str(), ctx.phase)) {
+                ctx.stop_propagation();
+            }
+        }
+    }
+
+    impl EntityBehavior for Tracer {
+        fn capture_event(
+            &mut self,
+            e: &mut Entity,
+            _app: &mut Option<&mut App>,
+            _ev: &Event,
+            ctx: &mut EventContext,
+        ) {
+            self.visit(e, ctx);
+        }
+
+        fn event(
+            &mut self,
+            e: &mut Entity,
+            _app: &mut Option<&mut App>,
+            _ev: &Event,
+            ctx: &mut EventContext,
+        ) {
+            self.visit(e, ctx);
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    /// root with children a and b, a has a child a1
+    fn traced_tree(log: &PhaseLog, stop_at: Option<(&'static str, EventPhase)>) -> Entity {
+        let traced = |name: &str| {
+            Entity::with_behavior(
+                name,
+                Tracer {
+                    log: log.clone(),
+                    stop_at,
+                },
+            )
+        };
+        let mut a = traced("a");
+        a.add_child(traced("a1"));
+        let mut root = traced("root");
+        root.add_child(a);
+        root.add_child(traced("b"));
+        root
+    }
+
+    fn lost_focus() -> Event {
+        Event::SFMLEvent(SFMLEvent::LostFocus)
+    }
+
+    #[test]
+    fn events_are_captured_down_and_bubbled_up() {
+        let log = PhaseLog::default();
+        let mut root = traced_tree(&log, None);
+
+        root.handle_event(&mut None, &lost_focus());
+        assert_eq!(
+            *log.borrow(),
+            [
+                "root:Capture",
+                "a:Capture",
+                "a1:Capture",
+                "a1:Bubble",
+                "a:Bubble",
+                "b:Capture",
+                "b:Bubble",
+                "root:Bubble",
+            ]
+        );
+    }
+
+    #[test]
+    fn stopping_in_capture_hides_the_event_from_children() {
+        let log = PhaseLog::default();
+        let mut root = traced_tree(&log, Some(("a", EventPhase::Capture)));
+
+        root.handle_event(&mut None, &lost_focus());
+        assert_eq!(*log.borrow(), ["root:Capture", "a:Capture"]);
+    }
+
+    #[test]
+    fn stopping_in_bubble_hides_the_event_from_later_entities() {
+        let log = PhaseLog::default();
+        let mut root = traced_tree(&log, Some(("a1", EventPhase::Bubble)));
+
+        root.handle_event(&mut None, &lost_focus());
+        assert_eq!(
+            *log.borrow(),
+            ["root:Capture", "a:Capture", "a1:Capture", "a1:Bubble"]
+        );
+    }
+
+    #[test]
+    fn pointer_events_visit_children_topmost_first() {
+        let log = PhaseLog::default();
+        let mut root = traced_tree(&log, None);
+
+        let moved = Event::SFMLEvent(SFMLEvent::MouseMoved { x: 0, y: 0 });
+        root.handle_event(&mut None, &moved);
+        let order: Vec<_> = log
+            .borrow()
+            .iter()
+            .filter(|l| l.ends_with("Capture"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            order,
+            ["root:Capture", "b:Capture", "a:Capture", "a1:Capture"]
+        );
+    }
+
+    type DetachLog = Rc<RefCell<Vec<String>>>;
+
+    struct Probe {
+        name: String,
+        log: DetachLog,
+    }
+
+    impl Component for Probe {
+        fn get_name(&self) -> &str {
+            &self.name
+        }
+
+        fn on_detach(&mut self, e: &mut Entity) {
+            self.log
+                .borrow_mut()
+                .push(format!("{}/{}", e.name, self.name));
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    struct ProbeBehavior {
+        log: DetachLog,
+    }
+
+    impl EntityBehavior for ProbeBehavior {
+        fn on_detach(&mut self, e: &mut Entity) {
+            self.log.borrow_mut().push(e.name.clone());
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn probed(name: &str, log: &DetachLog) -> Entity {
+        let mut e = Entity::with_behavior(name, ProbeBehavior { log: log.clone() });
+        e.add_component(Probe {
+            name: "probe".to_string(),
+            log: log.clone(),
+        });
+        e
+    }
+
+    #[test]
+    fn despawn_detaches_the_whole_subtree() {
+        let log = DetachLog::default();
+        let mut grandchild = probed("grandchild", &log);
+        grandchild.add_component(Probe {
+            name: "extra".to_string(),
+            log: log.clone(),
+        });
+        let mut child = probed("child", &log);
+        child.add_child(grandchild);
+        let id = child.id();
+
+        let mut root = probed("root", &log);
+        root.add_child(child);
+
+        assert!(root.despawn_child(id));
+        assert!(!root.despawn_child(id));
+        assert_eq!(
+            *log.borrow(),
+            [
+                "child",
+                "child/probe",
+                "grandchild",
+                "grandchild/probe",
+                "grandchild/extra",
+            ]
+        );
+    }
+
+    #[test]
+    fn take_child_keeps_components_attached() {
+        // Reparenting takes the Entity out and adds it back elsewhere
+        let log = DetachLog::default();
+        let child = probed("child", &log);
+        let id = child.id();
+        let mut root = probed("root", &log);
+        root.add_child(child);
+
+        let mut taken = root.take_child(id).unwrap();
+        assert_eq!(*log.borrow(), ["child"]);
+        assert!(taken.get_named_mut::<Probe>("probe").is_some());
+    }
+
+    #[test]
+    fn index_maps_paths_and_ids_both_ways() {
+        let leaf = Entity::new("leaf");
+        let leaf_id = leaf.id();
+        let mut branch = Entity::new("branch");
+        branch.add_child(leaf);
+        let mut root = Entity::new("root");
+        root.add_child(branch);
+
+        let index = EntityIndex::build(&root);
+        assert_eq!(index.id_of("branch/leaf"), Ok(leaf_id));
+        assert_eq!(index.path_of(leaf_id), Ok("branch/leaf"));
+        assert_eq!(index.id_of(""), Ok(root.id()));
+        assert_eq!(index.path_of(root.id()), Ok(""));
+    }
+
+    #[test]
+    fn index_ignores_extra_slashes() {
+        let leaf = Entity::new("leaf");
+        let leaf_id = leaf.id();
+        let mut root = Entity::new("root");
+        root.add_child(leaf);
+
+        let index = EntityIndex::build(&root);
+        assert_eq!(index.id_of("/leaf/"), Ok(leaf_id));
+        assert_eq!(index.id_of("//leaf"), Ok(leaf_id));
+    }
+
+    #[test]
+    fn index_resolves_duplicate_paths_to_the_first_entity() {
+        let first = Entity::new("twin");
+        let second = Entity::new("twin");
+        let (first_id, second_id) = (first.id(), second.id());
+        let mut root = Entity::new("root");
+        root.add_child(first);
+        root.add_child(second);
+
+        let index = EntityIndex::build(&root);
+        assert_eq!(index.id_of("twin"), Ok(first_id));
+        assert_eq!(index.path_of(second_id), Ok("twin"));
+    }
+
+    #[test]
+    fn index_reports_unknown_paths_and_ids() {
+        let outsider = Entity::new("outsider");
+        let index = EntityIndex::build(&Entity::new("root"));
+
+        assert_eq!(
+            index.id_of("missing"),
+            Err(EntityLookupError::PathNotFound("missing".to_string()))
+        );
+        assert_eq!(
+            index.path_of(outsider.id()),
+            Err(EntityLookupError::IdNotFound(outsider.id()))
+        );
+    }
+
+    #[test]
+    fn index_goes_stale_when_entities_are_added_or_removed() {
+        assert!(EntityIndex::default().is_stale());
+
+        let mut root = Entity::new("root");
+        let index = EntityIndex::build(&root);
+        let child = Entity::new("child");
+        let id = child.id();
+        root.add_child(child);
+        assert!(index.is_stale());
+
+        let index = EntityIndex::build(&root);
+        root.take_child(id);
+        assert!(index.is_stale());
+    }
+}
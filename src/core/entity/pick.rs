@@ -0,0 +1,71 @@
+use crate::core::component::pickable::Pickable;
+use crate::core::component::render_target::RenderTarget;
+use crate::core::component::transform::inverse_transform_point;
+use crate::core::entity::{Entity, EntityChild, EntityId};
+use crate::core::resource::Resources;
+
+/// Topmost Pickable Entity under a screen space point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerHit {
+    pub id: EntityId,
+
+    /// The point in the Entity's local space
+    pub local: (f32, f32),
+}
+
+/// Hit tests the tree against the world transforms of the most recent render. Entities are
+/// visited in render order so the last hit is the topmost one. Hidden and inactive Entities,
+/// and their subtrees, are skipped along with subtrees rendered into a RenderTarget.
+pub fn pick(root: &Entity, resources: &Resources, point: (f32, f32)) -> Option<PointerHit> {
+    pick_with(root, resources, point, None, &|e| e.has::<Pickable>())
+}
+
+/// Same as `pick` for the Entities accepted by `target` instead of Pickable ones. The subtree of
+/// `exclude` is skipped, eg: the Entity being dragged. Pickable alpha thresholds still apply.
+pub fn pick_with(
+    root: &Entity,
+    resources: &Resources,
+    point: (f32, f32),
+    exclude: Option<EntityId>,
+    target: &dyn Fn(&Entity) -> bool,
+) -> Option<PointerHit> {
+    let mut hit = None;
+    visit(root, resources, point, exclude, target, &mut hit);
+    hit
+}
+
+fn visit(
+    e: &Entity,
+    resources: &Resources,
+    point: (f32, f32),
+    exclude: Option<EntityId>,
+    target: &dyn Fn(&Entity) -> bool,
+    hit: &mut Option<PointerHit>,
+) {
+    // Subtrees rendered into a RenderTarget are not on screen where their transforms say
+    if !e.visible || !e.active || exclude == Some(e.id) || e.has::<RenderTarget>() {
+        return;
+    }
+
+    let is_target = target(e);
+    let alpha_threshold = e.get::<Pickable>().and_then(|p| p.alpha_threshold);
+
+    for c in &e.children {
+        match c {
+            EntityChild::Entity(child) => visit(child, resources, point, exclude, target, hit),
+            EntityChild::Component(cmp) => {
+                let is_hit = match (is_target, cmp.hit_quad()) {
+                    (true, Some(quad)) => quad.hit(resources, &e.world, point, alpha_threshold),
+                    _ => false,
+                };
+
+                if is_hit {
+                    *hit = Some(PointerHit {
+                        id: e.id,
+                        local: inverse_transform_point(&e.world, point),
+                    });
+                }
+            }
+        }
+    }
+}
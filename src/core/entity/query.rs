@@ -0,0 +1,185 @@
+use crate::core::component::Component;
+use crate::core::entity::{Entity, EntityChild, EntityId};
+
+use std::any::TypeId;
+
+/// Filter over an Entity tree, eg: `Query::new().tag("enemy").with::<Image>().without::<Text>()`.
+/// Queries visit parents before their children and include inactive Entities.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    with: Vec<TypeId>,
+    without: Vec<TypeId>,
+    tags: Vec<String>,
+    without_tags: Vec<String>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match Entities with at least one component of type T
+    pub fn with<T: Component + 'static>(mut self) -> Self {
+        self.with.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Only match Entities without any component of type T
+    pub fn without<T: Component + 'static>(mut self) -> Self {
+        self.without.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Only match Entities carrying the tag
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Only match Entities not carrying the tag
+    pub fn without_tag(mut self, tag: &str) -> Self {
+        self.without_tags.push(tag.to_string());
+        self
+    }
+
+    pub fn matches(&self, e: &Entity) -> bool {
+        if !self.tags.iter().all(|t| e.has_tag(t)) || self.without_tags.iter().any(|t| e.has_tag(t))
+        {
+            return false;
+        }
+
+        let has = |t: &TypeId| e.component_index.contains_key(t);
+        self.with.iter().all(has) && !self.without.iter().any(has)
+    }
+
+    /// Calls `f` with `root` and every descendant matching the query.
+    pub fn for_each(&self, root: &mut Entity, f: &mut impl FnMut(&mut Entity)) {
+        if self.matches(root) {
+            f(root);
+        }
+
+        for c in &mut root.children {
+            if let EntityChild::Entity(ent) = c {
+                self.for_each(ent, f);
+            }
+        }
+    }
+
+    /// Calls `f` with every component of type T on the matching Entities.
+    pub fn for_each_component<T: Component + 'static>(
+        &self,
+        root: &mut Entity,
+        f: &mut impl FnMut(EntityId, &mut T),
+    ) {
+        self.for_each(root, &mut |e| {
+            let id = e.id();
+            for typed_cmp in e.get_all_mut::<T>() {
+                f(id, typed_cmp);
+            }
+        });
+    }
+
+    /// Ids of every matching Entity, for use with `Entity::find_by_id` or deferred commands.
+    pub fn ids(&self, root: &mut Entity) -> Vec<EntityId> {
+        let mut ids = Vec::new();
+        self.for_each(root, &mut |e| ids.push(e.id()));
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::any::Any;
+
+    struct Marker;
+
+    impl Component for Marker {
+        fn get_name(&self) -> &str {
+            "marker"
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    struct Other;
+
+    impl Component for Other {
+        fn get_name(&self) -> &str {
+            "other"
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn entity(tags: &[&str], marked: bool) -> Entity {
+        let mut e = Entity::new("e");
+        for tag in tags {
+            e.add_tag(tag);
+        }
+        if marked {
+            e.add_component(Marker);
+        }
+        e
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(Query::new().matches(&entity(&[], false)));
+        assert!(Query::new().matches(&entity(&["enemy"], true)));
+    }
+
+    #[test]
+    fn matches_components() {
+        let marked = entity(&[], true);
+        let plain = entity(&[], false);
+
+        assert!(Query::new().with::<Marker>().matches(&marked));
+        assert!(!Query::new().with::<Marker>().matches(&plain));
+        assert!(!Query::new().without::<Marker>().matches(&marked));
+        assert!(Query::new().without::<Marker>().matches(&plain));
+
+        // Every `with` must be present
+        assert!(!Query::new()
+            .with::<Marker>()
+            .with::<Other>()
+            .matches(&marked));
+    }
+
+    #[test]
+    fn matches_tags() {
+        let enemy = entity(&["enemy", "flying"], false);
+
+        assert!(Query::new().tag("enemy").tag("flying").matches(&enemy));
+        assert!(!Query::new().tag("enemy").tag("boss").matches(&enemy));
+        assert!(!Query::new().without_tag("flying").matches(&enemy));
+        assert!(Query::new()
+            .tag("enemy")
+            .without_tag("boss")
+            .matches(&enemy));
+    }
+
+    #[test]
+    fn visits_matching_descendants_parents_first() {
+        let mut root = entity(&["enemy"], false);
+        let mut child = entity(&[], true);
+        child.add_child(entity(&["enemy"], true));
+        let child_id = child.id();
+        root.add_child(child);
+
+        let ids = Query::new().with::<Marker>().ids(&mut root);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], child_id);
+
+        let mut count = 0;
+        Query::new()
+            .tag("enemy")
+            .for_each_component::<Marker>(&mut root, &mut |_, _| count += 1);
+        assert_eq!(count, 1);
+    }
+}
@@ -1,5 +1,12 @@
+use sfml::window::mouse::Button;
 use sfml::window::Event as SFMLEvent;
 
+use std::any::{type_name, Any};
+use std::rc::Rc;
+
+use super::entity::EntityId;
+use super::input::gamepad::{GamepadAxis, GamepadButton};
+use super::scheduler::TimerPayload;
 use super::{renderer::window::WindowConfig, resource::AsyncLoadHandle};
 
 #[derive(Debug, Clone, Copy)]
@@ -10,6 +17,132 @@ pub struct ImageLoadEventPayload {
     pub height: u32,
 }
 
+#[derive(Debug, Clone)]
+pub struct ActionAxisPayload {
+    pub axis: String,
+
+    /// Combined value of every input bound to the axis, in the range -1..1
+    pub value: f32,
+}
+
+/// Event payload defined by game code. The engine only carries it, handlers recover the concrete
+/// type with `downcast_ref`.
+#[derive(Clone)]
+pub struct CustomEvent {
+    payload: Rc<dyn Any>,
+    type_name: &'static str,
+}
+
+impl CustomEvent {
+    pub fn new<T: Any>(payload: T) -> Self {
+        Self {
+            payload: Rc::new(payload),
+            type_name: type_name::<T>(),
+        }
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.payload.downcast_ref()
+    }
+}
+
+impl std::fmt::Debug for CustomEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CustomEvent({})", self.type_name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadButtonPayload {
+    pub gamepad: u32,
+    pub button: GamepadButton,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadAxisPayload {
+    pub gamepad: u32,
+    pub axis: GamepadAxis,
+
+    /// Position in the range -1..1 with the deadzone applied
+    pub value: f32,
+}
+
+/// Pointer interaction with a Pickable Entity, see core::pointer
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, Clone, Copy)]
+pub struct PointerPayload {
+    /// Entity under the pointer. The event is delivered to its whole subtree.
+    pub entity: EntityId,
+
+    /// Screen space position
+    pub x: f32,
+    pub y: f32,
+
+    /// Position in the Entity's local space
+    pub local: (f32, f32),
+
+    /// Mouse button for Pressed, Released and Click events
+    pub button: Option<Button>,
+}
+
+/// Drag gesture of a Draggable Entity, see core::drag
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, Clone, Copy)]
+pub struct DragPayload {
+    /// Dragged Entity. The event is delivered to its whole subtree.
+    pub entity: EntityId,
+
+    /// Screen space pointer position
+    pub x: f32,
+    pub y: f32,
+
+    /// Position of the Entity in its parent's space, with the Draggable constraints applied
+    pub position: (f32, f32),
+
+    /// Drop target under the pointer
+    pub target: Option<EntityId>,
+
+    /// Whether `target` accepts the payload of the dragged Entity
+    pub accepted: bool,
+}
+
+/// A dragged Entity over a DropTarget, see core::drag
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub struct DropPayload {
+    pub source: EntityId,
+
+    /// Drop target Entity. The event is delivered to its whole subtree.
+    pub target: EntityId,
+
+    /// Payload kind of the dragged Entity
+    pub payload: String,
+    pub accepted: bool,
+}
+
+/// Editing input for the focused Entity, see core::focus
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextInput {
+    /// Typed or pasted text, never contains control characters
+    Insert(String),
+    Backspace,
+    Delete,
+    CursorLeft,
+    CursorRight,
+    Home,
+    End,
+
+    /// Enter was pressed
+    Submit,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextInputPayload {
+    /// Focused Entity. The event is delivered to its whole subtree.
+    pub entity: EntityId,
+    pub input: TextInput,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum UpdateRenderablePayload {
     X(f32),
@@ -39,4 +172,137 @@ pub enum Event {
 
     // Window has been changed
     WindowUpdated(WindowConfig),
+
+    /// A named input action became active, see core::input::action
+    ActionPressed(String),
+
+    /// A named input action is no longer held by any of its bindings
+    ActionReleased(String),
+
+    /// A named input axis changed value
+    ActionAxis(ActionAxisPayload),
+
+    /// A controller was connected, see core::input::gamepad
+    GamepadConnected(u32),
+
+    GamepadDisconnected(u32),
+
+    /// A button from the standard gamepad layout changed state
+    GamepadButtonPressed(GamepadButtonPayload),
+    GamepadButtonReleased(GamepadButtonPayload),
+
+    /// An axis from the standard gamepad layout moved outside of the deadzone, or back to rest
+    GamepadAxisMoved(GamepadAxisPayload),
+
+    /// Game defined event posted with `App::post_custom_event`
+    Custom(CustomEvent),
+
+    /// A timer started with `App::scheduler` fired, delivered to the subtree of the Entity it belongs to
+    Timer(TimerPayload),
+
+    /// The pointer moved onto a Pickable Entity
+    PointerEnter(PointerPayload),
+
+    /// The pointer moved off a Pickable Entity, or onto one above it
+    PointerLeave(PointerPayload),
+
+    /// The pointer moved while over a Pickable Entity
+    #[cfg_attr(not(test), allow(dead_code))]
+    PointerHover(PointerPayload),
+
+    PointerPressed(PointerPayload),
+    #[cfg_attr(not(test), allow(dead_code))]
+    PointerReleased(PointerPayload),
+
+    /// A button was pressed and released over the same Pickable Entity
+    PointerClick(PointerPayload),
+
+    /// The Entity took keyboard focus
+    FocusGained(EntityId),
+
+    /// The Entity lost keyboard focus
+    FocusLost(EntityId),
+
+    /// Text editing input for the focused Entity
+    TextInput(TextInputPayload),
+
+    /// The pointer moved past the Draggable threshold while pressed on the Entity
+    DragStart(DragPayload),
+
+    /// The dragged Entity moved
+    #[cfg_attr(not(test), allow(dead_code))]
+    DragMove(DragPayload),
+
+    /// The drag ended, either by a release or because the window lost focus
+    DragEnd(DragPayload),
+
+    /// A dragged Entity moved onto the drop target
+    #[cfg_attr(not(test), allow(dead_code))]
+    DragEnter(DropPayload),
+
+    /// A dragged Entity moved off the drop target, or was released on it without being accepted
+    #[cfg_attr(not(test), allow(dead_code))]
+    DragLeave(DropPayload),
+
+    /// An accepted Entity was released on the drop target
+    #[cfg_attr(not(test), allow(dead_code))]
+    Drop(DropPayload),
+}
+
+impl Event {
+    /// Pointer events are delivered to Entities in reverse z-order so the topmost Entity sees them first
+    pub fn is_pointer_event(&self) -> bool {
+        matches!(
+            self,
+            Event::SFMLEvent(
+                SFMLEvent::MouseMoved { .. }
+                    | SFMLEvent::MouseButtonPressed { .. }
+                    | SFMLEvent::MouseButtonReleased { .. }
+                    | SFMLEvent::MouseWheelScrolled { .. }
+                    | SFMLEvent::TouchBegan { .. }
+                    | SFMLEvent::TouchMoved { .. }
+                    | SFMLEvent::TouchEnded { .. }
+            )
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    /// Travelling down the tree, parents see the event before their children
+    Capture,
+
+    /// Travelling back up the tree, children see the event before their parents
+    Bubble,
+}
+
+/// Propagation state shared by every Entity an event is delivered to
+#[derive(Debug)]
+pub struct EventContext {
+    pub phase: EventPhase,
+    handled: bool,
+}
+
+impl Default for EventContext {
+    fn default() -> Self {
+        EventContext::new(EventPhase::Capture)
+    }
+}
+
+impl EventContext {
+    pub fn new(phase: EventPhase) -> Self {
+        Self {
+            phase,
+            handled: false,
+        }
+    }
+
+    /// Marks the event as handled. No further Entities will receive it in either phase.
+    pub fn stop_propagation(&mut self) {
+        self.handled = true;
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.handled
+    }
 }
@@ -0,0 +1,126 @@
+use crate::core::entity::EntityId;
+use crate::core::event::Event;
+
+use std::cell::{Cell, RefCell};
+
+/// Queued events with a higher priority are delivered first. Events of equal priority are
+/// delivered in the order they were posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum EventPriority {
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventTarget {
+    /// Delivered to the whole tree from the root Entity
+    Broadcast,
+
+    /// Delivered to the subtree of the Entity at a slash separated path from the root, eg:
+    /// "entry/testbed". Resolved when the event is delivered, not when it is posted.
+    Path(String),
+
+    /// Delivered to the subtree of the Entity with the provided id
+    Entity(EntityId),
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedEvent {
+    pub event: Event,
+    pub target: EventTarget,
+    pub priority: EventPriority,
+    sequence: u64,
+}
+
+/// Events posted by game or engine code, delivered once on the next event pump. Posting only needs
+/// `&self` so `update` can post as well as event handlers.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    queue: RefCell<Vec<QueuedEvent>>,
+    next_sequence: Cell<u64>,
+}
+
+impl EventQueue {
+    pub fn post(&self, target: EventTarget, event: Event, priority: EventPriority) {
+        let sequence = self.next_sequence.get();
+        self.next_sequence.set(sequence + 1);
+
+        self.queue.borrow_mut().push(QueuedEvent {
+            event,
+            target,
+            priority,
+            sequence,
+        });
+    }
+
+    /// Empties the queue and returns its events in delivery order. Anything posted while these
+    /// are delivered waits for the next drain.
+    pub fn drain(&self) -> Vec<QueuedEvent> {
+        let mut events = self.queue.take();
+        events.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.sequence.cmp(&b.sequence))
+        });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Posts a GamepadConnected event, the gamepad id tells the events apart
+    fn post(queue: &EventQueue, gamepad: u32, priority: EventPriority) {
+        queue.post(
+            EventTarget::Broadcast,
+            Event::GamepadConnected(gamepad),
+            priority,
+        );
+    }
+
+    /// Gamepad ids of the drained events
+    fn order(events: Vec<QueuedEvent>) -> Vec<u32> {
+        events
+            .into_iter()
+            .map(|queued| match queued.event {
+                Event::GamepadConnected(gamepad) => gamepad,
+                e => panic!("unexpected event {:?}", e),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drains_by_priority_then_posting_order() {
+        let queue = EventQueue::default();
+        post(&queue, 1, EventPriority::Normal);
+        post(&queue, 2, EventPriority::High);
+        post(&queue, 3, EventPriority::Normal);
+        post(&queue, 4, EventPriority::High);
+
+        assert_eq!(order(queue.drain()), [2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn events_are_delivered_once() {
+        let queue = EventQueue::default();
+        post(&queue, 1, EventPriority::Normal);
+        assert_eq!(order(queue.drain()), [1]);
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn events_posted_after_a_drain_keep_their_order() {
+        let queue = EventQueue::default();
+        post(&queue, 1, EventPriority::Normal);
+        let first = queue.drain();
+
+        // Posted while the first batch is delivered
+        post(&queue, 2, EventPriority::Normal);
+        post(&queue, 3, EventPriority::Normal);
+
+        assert_eq!(order(first), [1]);
+        assert_eq!(order(queue.drain()), [2, 3]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::core::component::focusable::Focusable;
+use crate::core::entity::{Entity, EntityId};
+use crate::core::event::TextInput;
+
+use sfml::window::{clipboard, Event as SFMLEvent, Key};
+use std::cell::Cell;
+
+/// Editing input produced by a keyboard event. Pasting reads the system clipboard.
+pub fn text_input(ev: &SFMLEvent) -> Option<TextInput> {
+    match *ev {
+        // Backspace, Enter, Delete and Ctrl shortcuts also arrive as control characters
+        SFMLEvent::TextEntered { unicode } if !unicode.is_control() => {
+            Some(TextInput::Insert(unicode.to_string()))
+        }
+        SFMLEvent::KeyPressed {
+            code: Key::V,
+            ctrl,
+            system,
+            ..
+        } if ctrl || system => {
+            let pasted: String = clipboard::get_string()
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect();
+            (!pasted.is_empty()).then_some(TextInput::Insert(pasted))
+        }
+        SFMLEvent::KeyPressed { code, .. } => match code {
+            Key::Backspace => Some(TextInput::Backspace),
+            Key::Delete => Some(TextInput::Delete),
+            Key::Left => Some(TextInput::CursorLeft),
+            Key::Right => Some(TextInput::CursorRight),
+            Key::Home => Some(TextInput::Home),
+            Key::End => Some(TextInput::End),
+            Key::Enter => Some(TextInput::Submit),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Focusable Entities in Tab order. Hidden and inactive subtrees are skipped.
+pub fn focus_chain(root: &mut Entity) -> Vec<EntityId> {
+    fn visit(e: &mut Entity, chain: &mut Vec<(i32, EntityId)>) {
+        if !e.visible || !e.active {
+            return;
+        }
+
+        if let Some(focusable) = e.get::<Focusable>() {
+            chain.push((focusable.tab_index, e.id()));
+        }
+
+        for child in e.children_mut() {
+            visit(child, chain);
+        }
+    }
+
+    let mut chain = Vec::new();
+    visit(root, &mut chain);
+
+    // Stable so equal tab indices keep tree order
+    chain.sort_by_key(|(tab_index, _)| *tab_index);
+    chain.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Whether the Entity with `id` is in the tree with no hidden or inactive Entity on the way to it
+pub fn is_reachable(root: &mut Entity, id: EntityId) -> bool {
+    if !root.visible || !root.active {
+        return false;
+    }
+
+    root.id() == id || root.children_mut().any(|child| is_reachable(child, id))
+}
+
+/// Entity which owns keyboard input. Only needs `&self` to change so focus can be moved from
+/// `update` as well as event handlers.
+#[derive(Debug, Default)]
+pub struct Focus {
+    focused: Cell<Option<EntityId>>,
+}
+
+impl Focus {
+    pub fn focused(&self) -> Option<EntityId> {
+        self.focused.get()
+    }
+
+    /// Returns the previously focused Entity
+    pub fn replace(&self, id: Option<EntityId>) -> Option<EntityId> {
+        self.focused.replace(id)
+    }
+
+    /// Entity after the focused one in `chain`, wrapping around. Starts from either end of the
+    /// chain when nothing in it is focused.
+    pub fn next_in_chain(&self, chain: &[EntityId], backwards: bool) -> Option<EntityId> {
+        if chain.is_empty() {
+            return None;
+        }
+
+        let last = chain.len() - 1;
+        let position = self
+            .focused()
+            .and_then(|id| chain.iter().position(|c| *c == id));
+
+        let next = match (position, backwards) {
+            (None, false) => 0,
+            (None, true) => last,
+            (Some(p), false) if p == last => 0,
+            (Some(p), false) => p + 1,
+            (Some(0), true) => last,
+            (Some(p), true) => p - 1,
+        };
+        Some(chain[next])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focusable(name: &str, tab_index: i32) -> Entity {
+        let mut e = Entity::new(name);
+        e.add_component(Focusable { tab_index });
+        e
+    }
+
+    fn ids(count: usize) -> Vec<EntityId> {
+        (0..count).map(|_| Entity::new("e").id()).collect()
+    }
+
+    #[test]
+    fn next_in_chain_starts_from_either_end() {
+        let chain = ids(3);
+        let focus = Focus::default();
+
+        assert_eq!(focus.next_in_chain(&chain, false), Some(chain[0]));
+        assert_eq!(focus.next_in_chain(&chain, true), Some(chain[2]));
+        assert_eq!(focus.next_in_chain(&[], false), None);
+    }
+
+    #[test]
+    fn next_in_chain_steps_and_wraps() {
+        let chain = ids(3);
+        let focus = Focus::default();
+
+        focus.replace(Some(chain[1]));
+        assert_eq!(focus.next_in_chain(&chain, false), Some(chain[2]));
+        assert_eq!(focus.next_in_chain(&chain, true), Some(chain[0]));
+
+        focus.replace(Some(chain[2]));
+        assert_eq!(focus.next_in_chain(&chain, false), Some(chain[0]));
+
+        focus.replace(Some(chain[0]));
+        assert_eq!(focus.next_in_chain(&chain, true), Some(chain[2]));
+    }
+
+    #[test]
+    fn next_in_chain_restarts_when_focus_is_outside_the_chain() {
+        let chain = ids(2);
+        let focus = Focus::default();
+        focus.replace(Some(Entity::new("outside").id()));
+
+        assert_eq!(focus.next_in_chain(&chain, false), Some(chain[0]));
+        assert_eq!(focus.next_in_chain(&chain, true), Some(chain[1]));
+    }
+
+    #[test]
+    fn focus_chain_orders_by_tab_index_and_skips_hidden() {
+        let late = focusable("late", 1);
+        let first = focusable("first", 0);
+        let second = focusable("second", 0);
+        let mut hidden = focusable("hidden", 0);
+        hidden.visible = false;
+        let ids = (late.id(), first.id(), second.id());
+
+        let mut root = Entity::new("root");
+        root.add_child(late);
+        root.add_child(first);
+        root.add_child(hidden);
+        root.add_child(second);
+
+        assert_eq!(focus_chain(&mut root), [ids.1, ids.2, ids.0]);
+    }
+}
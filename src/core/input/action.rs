@@ -0,0 +1,539 @@
+use crate::core::event::{ActionAxisPayload, Event};
+use crate::core::input::gamepad::{GamepadAxis, GamepadButton};
+use crate::core::input::AxisDef;
+
+use serde::{Deserialize, Serialize};
+use sfml::window::joystick::Axis;
+use sfml::window::mouse::Button;
+use sfml::window::{Event as SFMLEvent, Key};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+fn default_threshold() -> f32 {
+    0.5
+}
+
+fn default_scale() -> f32 {
+    1.
+}
+
+/// Physical input which drives an action. A `joystick` or `gamepad` of None matches every controller.
+/// Gamepad bindings use the standard layout, Joystick bindings use raw SFML indices.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Binding {
+    Key {
+        key: Key,
+    },
+    MouseButton {
+        button: Button,
+    },
+    JoystickButton {
+        #[serde(default)]
+        joystick: Option<u32>,
+        button: u32,
+    },
+    /// Held while the axis is past `threshold`. Negative thresholds trigger in the negative direction.
+    JoystickAxis {
+        #[serde(default)]
+        joystick: Option<u32>,
+        #[serde(with = "AxisDef")]
+        axis: Axis,
+        #[serde(default = "default_threshold")]
+        threshold: f32,
+    },
+    GamepadButton {
+        #[serde(default)]
+        gamepad: Option<u32>,
+        button: GamepadButton,
+    },
+    /// Held while the axis is past `threshold`. Negative thresholds trigger in the negative direction.
+    GamepadAxis {
+        #[serde(default)]
+        gamepad: Option<u32>,
+        axis: GamepadAxis,
+        #[serde(default = "default_threshold")]
+        threshold: f32,
+    },
+}
+
+/// Contribution of a Binding to a named axis. Buttons contribute `scale` while held and joystick
+/// axes contribute their position multiplied by `scale`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    #[serde(flatten)]
+    pub binding: Binding,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+/// Input state change pulled out of a window or gamepad event
+#[derive(Debug, Clone, Copy)]
+enum Input {
+    Key(Key, bool),
+    MouseButton(Button, bool),
+    JoystickButton(u32, u32, bool),
+    /// Axis position normalized to -1..1
+    JoystickAxis(u32, Axis, f32),
+    GamepadButton(u32, GamepadButton, bool),
+    GamepadAxis(u32, GamepadAxis, f32),
+}
+
+impl Input {
+    /// Controller the input came from, None for the keyboard and mouse
+    fn controller(&self) -> Option<u32> {
+        match *self {
+            Input::Key(..) | Input::MouseButton(..) => None,
+            Input::JoystickButton(id, ..)
+            | Input::JoystickAxis(id, ..)
+            | Input::GamepadButton(id, ..)
+            | Input::GamepadAxis(id, ..) => Some(id),
+        }
+    }
+
+    fn from_event(ev: &Event) -> Option<Self> {
+        let ev = match ev {
+            Event::SFMLEvent(ev) => ev,
+            Event::GamepadButtonPressed(p) => {
+                return Some(Input::GamepadButton(p.gamepad, p.button, true))
+            }
+            Event::GamepadButtonReleased(p) => {
+                return Some(Input::GamepadButton(p.gamepad, p.button, false))
+            }
+            Event::GamepadAxisMoved(p) => {
+                return Some(Input::GamepadAxis(p.gamepad, p.axis, p.value))
+            }
+            _ => return None,
+        };
+
+        match *ev {
+            SFMLEvent::KeyPressed { code, .. } => Some(Input::Key(code, true)),
+            SFMLEvent::KeyReleased { code, .. } => Some(Input::Key(code, false)),
+            SFMLEvent::MouseButtonPressed { button, .. } => Some(Input::MouseButton(button, true)),
+            SFMLEvent::MouseButtonReleased { button, .. } => {
+                Some(Input::MouseButton(button, false))
+            }
+            SFMLEvent::JoystickButtonPressed { joystickid, button } => {
+                Some(Input::JoystickButton(joystickid, button, true))
+            }
+            SFMLEvent::JoystickButtonReleased { joystickid, button } => {
+                Some(Input::JoystickButton(joystickid, button, false))
+            }
+            SFMLEvent::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            } => Some(Input::JoystickAxis(joystickid, axis, position / 100.)),
+            _ => None,
+        }
+    }
+}
+
+impl Binding {
+    /// Whether the binding is held after `input`, or None when `input` does not concern it
+    fn held(&self, input: &Input) -> Option<bool> {
+        let position = self.position(input)?;
+        match *self {
+            Binding::JoystickAxis { threshold, .. } | Binding::GamepadAxis { threshold, .. } => {
+                match threshold < 0. {
+                    true => Some(position <= threshold),
+                    false => Some(position >= threshold),
+                }
+            }
+            _ => Some(position != 0.),
+        }
+    }
+
+    /// Analog value of the binding after `input`, or None when `input` does not concern it
+    fn position(&self, input: &Input) -> Option<f32> {
+        let matches_controller = |j: Option<u32>, id: u32| j.is_none() || j == Some(id);
+        let down = |d: bool| if d { 1. } else { 0. };
+
+        match (*self, *input) {
+            (Binding::Key { key }, Input::Key(k, d)) if key == k => Some(down(d)),
+            (Binding::MouseButton { button }, Input::MouseButton(b, d)) if button == b => {
+                Some(down(d))
+            }
+            (Binding::JoystickButton { joystick, button }, Input::JoystickButton(id, b, d))
+                if button == b && matches_controller(joystick, id) =>
+            {
+                Some(down(d))
+            }
+            (Binding::JoystickAxis { joystick, axis, .. }, Input::JoystickAxis(id, a, p))
+                if axis == a && matches_controller(joystick, id) =>
+            {
+                Some(p)
+            }
+            (Binding::GamepadButton { gamepad, button }, Input::GamepadButton(id, b, d))
+                if button == b && matches_controller(gamepad, id) =>
+            {
+                Some(down(d))
+            }
+            (Binding::GamepadAxis { gamepad, axis, .. }, Input::GamepadAxis(id, a, p))
+                if axis == a && matches_controller(gamepad, id) =>
+            {
+                Some(p)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Named actions and axes with the inputs bound to them, as stored in a bindings config, eg:
+/// `{"actions": {"jump": [{"type": "Key", "key": "Space"}]}, "axes": {"move_x": [{"type": "JoystickAxis", "axis": "X"}]}}`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionBindings {
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl ActionBindings {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.actions
+            .entry(action.to_string())
+            .or_default()
+            .push(binding);
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: Binding, scale: f32) {
+        self.axes
+            .entry(axis.to_string())
+            .or_default()
+            .push(AxisBinding { binding, scale });
+    }
+
+    /// Removes every binding from an action or axis
+    pub fn unbind(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+    }
+}
+
+/// Binding index and the controller driving it, so controllers sharing a binding do not release
+/// or overwrite each other
+type BindingSource = (usize, Option<u32>);
+
+/// Translates window and gamepad events into action events using the current bindings
+#[derive(Debug, Default)]
+pub struct ActionMap {
+    bindings: ActionBindings,
+
+    /// Bindings currently held for each action
+    held: HashMap<String, HashSet<BindingSource>>,
+
+    /// Latest contribution of each binding for each axis
+    axis_inputs: HashMap<String, HashMap<BindingSource, f32>>,
+    axis_values: HashMap<String, f32>,
+}
+
+impl ActionMap {
+    pub fn bindings(&self) -> &ActionBindings {
+        &self.bindings
+    }
+
+    /// Replaces the bindings. Every action is treated as released and every axis as centered.
+    pub fn set_bindings(&mut self, bindings: ActionBindings) {
+        self.bindings = bindings;
+        self.held.clear();
+        self.axis_inputs.clear();
+        self.axis_values.clear();
+    }
+
+    pub fn is_pressed(&self, action: &str) -> bool {
+        matches!(self.held.get(action), Some(held) if !held.is_empty())
+    }
+
+    /// Current value of a named axis in the range -1..1
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axis_values.get(axis).copied().unwrap_or(0.)
+    }
+
+    /// Releases every action and centers every axis, eg: when the window loses focus and the
+    /// matching release events would never arrive.
+    pub fn release_all(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (action, held) in &mut self.held {
+            if !held.is_empty() {
+                held.clear();
+                events.push(Event::ActionReleased(action.clone()));
+            }
+        }
+
+        for (axis, value) in &mut self.axis_values {
+            if *value != 0. {
+                *value = 0.;
+                events.push(Event::ActionAxis(ActionAxisPayload {
+                    axis: axis.clone(),
+                    value: 0.,
+                }));
+            }
+        }
+        self.axis_inputs.clear();
+
+        events
+    }
+
+    /// Action events produced by an input event, in binding order
+    pub fn translate(&mut self, ev: &Event) -> Vec<Event> {
+        if let Event::SFMLEvent(SFMLEvent::LostFocus) = ev {
+            return self.release_all();
+        }
+
+        let input = match Input::from_event(ev) {
+            Some(input) => input,
+            None => return Vec::new(),
+        };
+
+        let source = input.controller();
+        let mut events = Vec::new();
+        for (action, bindings) in &self.bindings.actions {
+            let held = self.held.entry(action.clone()).or_default();
+            let was_pressed = !held.is_empty();
+
+            for (i, binding) in bindings.iter().enumerate() {
+                match binding.held(&input) {
+                    Some(true) => {
+                        held.insert((i, source));
+                    }
+                    Some(false) => {
+                        held.remove(&(i, source));
+                    }
+                    None => {}
+                }
+            }
+
+            match (was_pressed, !held.is_empty()) {
+                (false, true) => events.push(Event::ActionPressed(action.clone())),
+                (true, false) => events.push(Event::ActionReleased(action.clone())),
+                _ => {}
+            }
+        }
+
+        for (axis, bindings) in &self.bindings.axes {
+            let inputs = self.axis_inputs.entry(axis.clone()).or_default();
+
+            let mut touched = false;
+            for (i, binding) in bindings.iter().enumerate() {
+                if let Some(position) = binding.binding.position(&input) {
+                    inputs.insert((i, source), position * binding.scale);
+                    touched = true;
+                }
+            }
+
+            if touched {
+                let value = inputs.values().sum::<f32>().clamp(-1., 1.);
+                let previous = self.axis_values.insert(axis.clone(), value);
+                if previous.unwrap_or(0.) != value {
+                    events.push(Event::ActionAxis(ActionAxisPayload {
+                        axis: axis.clone(),
+                        value,
+                    }));
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::GamepadButtonPayload;
+
+    fn key(code: Key, down: bool) -> Event {
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        Event::SFMLEvent(match down {
+            true => SFMLEvent::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            false => SFMLEvent::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+        })
+    }
+
+    fn pad_button(gamepad: u32, button: GamepadButton, down: bool) -> Event {
+        let payload = GamepadButtonPayload { gamepad, button };
+        match down {
+            true => Event::GamepadButtonPressed(payload),
+            false => Event::GamepadButtonReleased(payload),
+        }
+    }
+
+    fn pad_axis(gamepad: u32, axis: GamepadAxis, value: f32) -> Event {
+        Event::GamepadAxisMoved(crate::core::event::GamepadAxisPayload {
+            gamepad,
+            axis,
+            value,
+        })
+    }
+
+    /// Action events as short strings so they can be compared
+    fn describe(events: Vec<Event>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|e| match e {
+                Event::ActionPressed(action) => format!("pressed {}", action),
+                Event::ActionReleased(action) => format!("released {}", action),
+                Event::ActionAxis(p) => format!("{} {}", p.axis, p.value),
+                e => format!("{:?}", e),
+            })
+            .collect()
+    }
+
+    fn action_map() -> ActionMap {
+        let mut bindings = ActionBindings::default();
+        bindings.bind("jump", Binding::Key { key: Key::Space });
+        bindings.bind("jump", Binding::Key { key: Key::W });
+        bindings.bind(
+            "jump",
+            Binding::GamepadButton {
+                gamepad: None,
+                button: GamepadButton::South,
+            },
+        );
+        bindings.bind_axis("move_x", Binding::Key { key: Key::A }, -1.);
+        bindings.bind_axis("move_x", Binding::Key { key: Key::D }, 1.);
+        bindings.bind_axis(
+            "move_x",
+            Binding::GamepadAxis {
+                gamepad: None,
+                axis: GamepadAxis::LeftX,
+                threshold: 0.5,
+            },
+            1.,
+        );
+
+        let mut actions = ActionMap::default();
+        actions.set_bindings(bindings);
+        actions
+    }
+
+    #[test]
+    fn action_stays_pressed_while_any_binding_is_held() {
+        let mut actions = action_map();
+        assert_eq!(
+            describe(actions.translate(&key(Key::Space, true))),
+            ["pressed jump"]
+        );
+        assert!(describe(actions.translate(&key(Key::W, true))).is_empty());
+        assert!(describe(actions.translate(&key(Key::Space, false))).is_empty());
+        assert!(actions.is_pressed("jump"));
+        assert_eq!(
+            describe(actions.translate(&key(Key::W, false))),
+            ["released jump"]
+        );
+        assert!(!actions.is_pressed("jump"));
+    }
+
+    #[test]
+    fn gamepads_sharing_a_binding_are_held_separately() {
+        let mut actions = action_map();
+        let south = GamepadButton::South;
+        assert_eq!(
+            describe(actions.translate(&pad_button(0, south, true))),
+            ["pressed jump"]
+        );
+        assert!(describe(actions.translate(&pad_button(1, south, true))).is_empty());
+
+        // Pad 1 still holds the button
+        assert!(describe(actions.translate(&pad_button(0, south, false))).is_empty());
+        assert!(actions.is_pressed("jump"));
+        assert_eq!(
+            describe(actions.translate(&pad_button(1, south, false))),
+            ["released jump"]
+        );
+    }
+
+    #[test]
+    fn axis_sums_and_clamps_bindings() {
+        let mut actions = action_map();
+        assert_eq!(
+            describe(actions.translate(&key(Key::D, true))),
+            ["move_x 1"]
+        );
+        assert_eq!(
+            describe(actions.translate(&key(Key::A, true))),
+            ["move_x 0"]
+        );
+        assert_eq!(
+            describe(actions.translate(&key(Key::D, false))),
+            ["move_x -1"]
+        );
+
+        // Pushing a stick right on top of A does not leave the range
+        actions.translate(&key(Key::A, false));
+        actions.translate(&pad_axis(0, GamepadAxis::LeftX, 1.));
+        actions.translate(&key(Key::D, true));
+        assert_eq!(actions.axis("move_x"), 1.);
+    }
+
+    #[test]
+    fn gamepads_sharing_an_axis_do_not_overwrite_each_other() {
+        let mut actions = action_map();
+        actions.translate(&pad_axis(0, GamepadAxis::LeftX, 0.5));
+        actions.translate(&pad_axis(1, GamepadAxis::LeftX, 0.25));
+        assert_eq!(actions.axis("move_x"), 0.75);
+
+        actions.translate(&pad_axis(0, GamepadAxis::LeftX, 0.));
+        assert_eq!(actions.axis("move_x"), 0.25);
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut actions = action_map();
+        actions.translate(&key(Key::Space, true));
+        actions.translate(&key(Key::D, true));
+
+        let events = describe(actions.translate(&Event::SFMLEvent(SFMLEvent::LostFocus)));
+        assert_eq!(events, ["released jump", "move_x 0"]);
+        assert!(!actions.is_pressed("jump"));
+        assert_eq!(actions.axis("move_x"), 0.);
+
+        // The release which arrives after focus returns is ignored
+        assert!(describe(actions.translate(&key(Key::Space, false))).is_empty());
+    }
+
+    #[test]
+    fn bindings_round_trip_through_json() {
+        let json = r#"{"actions": {"jump": [{"type": "Key", "key": "Space"}]},
+            "axes": {"move_x": [{"type": "GamepadAxis", "axis": "LeftX"}]}}"#;
+        let bindings: ActionBindings = serde_json::from_str(json).unwrap();
+        assert_eq!(bindings.actions["jump"], [Binding::Key { key: Key::Space }]);
+        assert_eq!(
+            bindings.axes["move_x"],
+            [AxisBinding {
+                binding: Binding::GamepadAxis {
+                    gamepad: None,
+                    axis: GamepadAxis::LeftX,
+                    threshold: 0.5,
+                },
+                scale: 1.,
+            }]
+        );
+
+        let saved = serde_json::to_string(&bindings).unwrap();
+        let loaded: ActionBindings = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.actions, bindings.actions);
+        assert_eq!(loaded.axes, bindings.axes);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::core::event::{Event, GamepadAxisPayload, GamepadButtonPayload};
+
+use serde::{Deserialize, Serialize};
+use sfml::window::joystick::{Axis, AXIS_COUNT, BUTTON_COUNT};
+use sfml::window::Event as SFMLEvent;
+use std::collections::{BTreeMap, HashMap};
+
+/// Axis positions closer to the center than this are reported as 0
+pub static DEFAULT_DEADZONE: f32 = 0.15;
+
+/// Standard gamepad buttons, named by position so they are the same across controller brands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Select,
+    Start,
+    Guide,
+    LeftStick,
+    RightStick,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+    DPadX,
+    DPadY,
+}
+
+/// Maps the raw SFML button indices and axes of a controller onto the standard layout
+#[derive(Debug, Clone)]
+pub struct GamepadLayout {
+    pub buttons: HashMap<u32, GamepadButton>,
+    pub axes: HashMap<Axis, GamepadAxis>,
+}
+
+impl Default for GamepadLayout {
+    /// Xbox style controller as reported by SFML
+    fn default() -> Self {
+        Self {
+            buttons: HashMap::from([
+                (0, GamepadButton::South),
+                (1, GamepadButton::East),
+                (2, GamepadButton::West),
+                (3, GamepadButton::North),
+                (4, GamepadButton::LeftShoulder),
+                (5, GamepadButton::RightShoulder),
+                (6, GamepadButton::Select),
+                (7, GamepadButton::Start),
+                (8, GamepadButton::Guide),
+                (9, GamepadButton::LeftStick),
+                (10, GamepadButton::RightStick),
+            ]),
+            axes: HashMap::from([
+                (Axis::X, GamepadAxis::LeftX),
+                (Axis::Y, GamepadAxis::LeftY),
+                (Axis::U, GamepadAxis::RightX),
+                (Axis::V, GamepadAxis::RightY),
+                (Axis::Z, GamepadAxis::LeftTrigger),
+                (Axis::R, GamepadAxis::RightTrigger),
+                (Axis::PovX, GamepadAxis::DPadX),
+                (Axis::PovY, GamepadAxis::DPadY),
+            ]),
+        }
+    }
+}
+
+impl GamepadLayout {
+    fn raw_button(&self, button: GamepadButton) -> Option<u32> {
+        self.buttons
+            .iter()
+            .find_map(|(raw, b)| (*b == button).then_some(*raw))
+    }
+
+    fn raw_axis(&self, axis: GamepadAxis) -> Option<Axis> {
+        self.axes
+            .iter()
+            .find_map(|(raw, a)| (*a == axis).then_some(*raw))
+    }
+}
+
+/// State of a single connected controller, indexed by raw SFML button and axis
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    id: u32,
+    buttons: [bool; BUTTON_COUNT as usize],
+
+    /// Positions in the range -1..1 with the deadzone already applied
+    axes: [f32; AXIS_COUNT as usize],
+}
+
+impl Gamepad {
+    fn new(id: u32) -> Self {
+        Self {
+            id,
+            buttons: [false; BUTTON_COUNT as usize],
+            axes: [0.; AXIS_COUNT as usize],
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn is_raw_button_down(&self, button: u32) -> bool {
+        self.buttons.get(button as usize).copied().unwrap_or(false)
+    }
+
+    pub fn raw_axis(&self, axis: Axis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+/// Every connected controller. State is driven purely by window events so controllers can be
+/// simulated by feeding synthetic joystick events to `handle_event`.
+#[derive(Debug)]
+pub struct Gamepads {
+    pads: BTreeMap<u32, Gamepad>,
+    pub layout: GamepadLayout,
+
+    /// Applied to axis movement as it arrives
+    pub deadzone: f32,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self {
+            pads: BTreeMap::new(),
+            layout: GamepadLayout::default(),
+            deadzone: DEFAULT_DEADZONE,
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn connected(&self) -> impl Iterator<Item = &Gamepad> {
+        self.pads.values()
+    }
+
+    pub fn is_connected(&self, id: u32) -> bool {
+        self.pads.contains_key(&id)
+    }
+
+    pub fn is_pressed(&self, id: u32, button: GamepadButton) -> bool {
+        match (self.pads.get(&id), self.layout.raw_button(button)) {
+            (Some(pad), Some(raw)) => pad.is_raw_button_down(raw),
+            _ => false,
+        }
+    }
+
+    /// Whether the button is held on any connected controller
+    pub fn any_pressed(&self, button: GamepadButton) -> bool {
+        self.pads.keys().any(|id| self.is_pressed(*id, button))
+    }
+
+    pub fn axis(&self, id: u32, axis: GamepadAxis) -> f32 {
+        match (self.pads.get(&id), self.layout.raw_axis(axis)) {
+            (Some(pad), Some(raw)) => pad.raw_axis(raw),
+            _ => 0.,
+        }
+    }
+
+    fn apply_deadzone(&self, position: f32) -> f32 {
+        let position = position.clamp(-1., 1.);
+        if position.abs() < self.deadzone {
+            return 0.;
+        }
+
+        // Rescale so the output still covers the full range past the deadzone
+        position.signum() * (position.abs() - self.deadzone) / (1. - self.deadzone)
+    }
+
+    /// Controllers which send input before their connect event are treated as connected
+    fn connect(&mut self, id: u32, events: &mut Vec<Event>) -> &mut Gamepad {
+        if !self.pads.contains_key(&id) {
+            events.push(Event::GamepadConnected(id));
+        }
+
+        self.pads.entry(id).or_insert_with(|| Gamepad::new(id))
+    }
+
+    /// Updates controller state from a raw window event and returns the gamepad events it produced
+    pub fn handle_event(&mut self, ev: &SFMLEvent) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        match *ev {
+            SFMLEvent::JoystickConnected { joystickid } => {
+                self.connect(joystickid, &mut events);
+            }
+            SFMLEvent::JoystickDisconnected { joystickid } => {
+                if let Some(pad) = self.pads.remove(&joystickid) {
+                    // Release everything so nothing stays held once the controller is gone
+                    for (raw, button) in &self.layout.buttons {
+                        if pad.is_raw_button_down(*raw) {
+                            events.push(Event::GamepadButtonReleased(GamepadButtonPayload {
+                                gamepad: joystickid,
+                                button: *button,
+                            }));
+                        }
+                    }
+
+                    for (raw, axis) in &self.layout.axes {
+                        if pad.raw_axis(*raw) != 0. {
+                            events.push(Event::GamepadAxisMoved(GamepadAxisPayload {
+                                gamepad: joystickid,
+                                axis: *axis,
+                                value: 0.,
+                            }));
+                        }
+                    }
+
+                    events.push(Event::GamepadDisconnected(joystickid));
+                }
+            }
+            SFMLEvent::JoystickButtonPressed { joystickid, button }
+            | SFMLEvent::JoystickButtonReleased { joystickid, button } => {
+                let down = matches!(ev, SFMLEvent::JoystickButtonPressed { .. });
+                let mapped = self.layout.buttons.get(&button).copied();
+                let pad = self.connect(joystickid, &mut events);
+
+                let changed = match pad.buttons.get_mut(button as usize) {
+                    Some(state) if *state != down => {
+                        *state = down;
+                        true
+                    }
+                    _ => false,
+                };
+
+                if let (true, Some(button)) = (changed, mapped) {
+                    let payload = GamepadButtonPayload {
+                        gamepad: joystickid,
+                        button,
+                    };
+                    events.push(match down {
+                        true => Event::GamepadButtonPressed(payload),
+                        false => Event::GamepadButtonReleased(payload),
+                    });
+                }
+            }
+            SFMLEvent::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            } => {
+                let value = self.apply_deadzone(position / 100.);
+                let mapped = self.layout.axes.get(&axis).copied();
+                let pad = self.connect(joystickid, &mut events);
+
+                if pad.axes[axis as usize] != value {
+                    pad.axes[axis as usize] = value;
+
+                    if let Some(axis) = mapped {
+                        events.push(Event::GamepadAxisMoved(GamepadAxisPayload {
+                            gamepad: joystickid,
+                            axis,
+                            value,
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(joystickid: u32, axis: Axis, position: f32) -> SFMLEvent {
+        SFMLEvent::JoystickMoved {
+            joystickid,
+            axis,
+            position,
+        }
+    }
+
+    #[test]
+    fn deadzone_zeroes_small_positions_and_rescales_the_rest() {
+        let pads = Gamepads {
+            deadzone: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(pads.apply_deadzone(0.19), 0.);
+        assert_eq!(pads.apply_deadzone(-0.19), 0.);
+        assert!((pads.apply_deadzone(0.6) - 0.5).abs() < 1e-6);
+        assert_eq!(pads.apply_deadzone(-1.), -1.);
+
+        // Out of range input is clamped
+        assert_eq!(pads.apply_deadzone(1.5), 1.);
+    }
+
+    #[test]
+    fn stick_drift_inside_the_deadzone_sends_no_events() {
+        let mut pads = Gamepads::default();
+        let events = pads.handle_event(&moved(0, Axis::X, 10.));
+
+        // Only the implicit connection, the position stays centered
+        assert!(matches!(events[..], [Event::GamepadConnected(0)]));
+        assert_eq!(pads.axis(0, GamepadAxis::LeftX), 0.);
+        assert!(pads.handle_event(&moved(0, Axis::X, -5.)).is_empty());
+    }
+
+    #[test]
+    fn axis_movement_is_reported_on_the_standard_layout() {
+        let mut pads = Gamepads::default();
+        pads.handle_event(&SFMLEvent::JoystickConnected { joystickid: 1 });
+
+        let events = pads.handle_event(&moved(1, Axis::U, 100.));
+        assert!(matches!(
+            events[..],
+            [Event::GamepadAxisMoved(GamepadAxisPayload {
+                gamepad: 1,
+                axis: GamepadAxis::RightX,
+                value,
+            })] if value == 1.
+        ));
+        assert_eq!(pads.axis(1, GamepadAxis::RightX), 1.);
+        assert_eq!(pads.axis(0, GamepadAxis::RightX), 0.);
+    }
+
+    #[test]
+    fn disconnect_releases_held_buttons_and_centers_axes() {
+        let mut pads = Gamepads::default();
+        pads.handle_event(&SFMLEvent::JoystickButtonPressed {
+            joystickid: 2,
+            button: 0,
+        });
+        pads.handle_event(&moved(2, Axis::Y, -100.));
+        assert!(pads.any_pressed(GamepadButton::South));
+
+        let events = pads.handle_event(&SFMLEvent::JoystickDisconnected { joystickid: 2 });
+        assert!(matches!(
+            events[..],
+            [
+                Event::GamepadButtonReleased(GamepadButtonPayload {
+                    gamepad: 2,
+                    button: GamepadButton::South,
+                }),
+                Event::GamepadAxisMoved(GamepadAxisPayload {
+                    gamepad: 2,
+                    axis: GamepadAxis::LeftY,
+                    value,
+                }),
+                Event::GamepadDisconnected(2),
+            ] if value == 0.
+        ));
+        assert!(!pads.is_connected(2));
+        assert!(!pads.any_pressed(GamepadButton::South));
+    }
+}
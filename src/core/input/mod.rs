@@ -0,0 +1,21 @@
+pub mod action;
+pub mod gamepad;
+pub mod recording;
+pub mod state;
+
+use serde::{Deserialize, Serialize};
+use sfml::window::joystick::Axis;
+
+/// Serde mirror of the SFML joystick Axis, use with `#[serde(with = "AxisDef")]`
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Axis")]
+pub(crate) enum AxisDef {
+    X,
+    Y,
+    Z,
+    R,
+    U,
+    V,
+    PovX,
+    PovY,
+}
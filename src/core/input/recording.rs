@@ -0,0 +1,363 @@
+use crate::core::entity::Entity;
+use crate::core::input::AxisDef;
+use crate::core::resource::Resources;
+use crate::core::scene::describe_entity;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sfml::window::joystick::Axis;
+use sfml::window::mouse::{Button, Wheel};
+use sfml::window::{Event as SFMLEvent, Key};
+
+/// Serializable copy of an SFML window event. Sensor events are not recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RecordedEvent {
+    Closed,
+    Resized {
+        width: u32,
+        height: u32,
+    },
+    LostFocus,
+    GainedFocus,
+    TextEntered {
+        unicode: char,
+    },
+    KeyPressed {
+        code: Key,
+        alt: bool,
+        ctrl: bool,
+        shift: bool,
+        system: bool,
+    },
+    KeyReleased {
+        code: Key,
+        alt: bool,
+        ctrl: bool,
+        shift: bool,
+        system: bool,
+    },
+    MouseWheelScrolled {
+        wheel: Wheel,
+        delta: f32,
+        x: i32,
+        y: i32,
+    },
+    MouseButtonPressed {
+        button: Button,
+        x: i32,
+        y: i32,
+    },
+    MouseButtonReleased {
+        button: Button,
+        x: i32,
+        y: i32,
+    },
+    MouseMoved {
+        x: i32,
+        y: i32,
+    },
+    MouseEntered,
+    MouseLeft,
+    JoystickButtonPressed {
+        joystickid: u32,
+        button: u32,
+    },
+    JoystickButtonReleased {
+        joystickid: u32,
+        button: u32,
+    },
+    JoystickMoved {
+        joystickid: u32,
+        #[serde(with = "AxisDef")]
+        axis: Axis,
+        position: f32,
+    },
+    JoystickConnected {
+        joystickid: u32,
+    },
+    JoystickDisconnected {
+        joystickid: u32,
+    },
+    TouchBegan {
+        finger: u32,
+        x: i32,
+        y: i32,
+    },
+    TouchMoved {
+        finger: u32,
+        x: i32,
+        y: i32,
+    },
+    TouchEnded {
+        finger: u32,
+        x: i32,
+        y: i32,
+    },
+}
+
+impl RecordedEvent {
+    /// None for events which can not be recorded
+    pub fn from_window_event(ev: &SFMLEvent) -> Option<Self> {
+        use RecordedEvent as R;
+        use SFMLEvent as S;
+
+        Some(match *ev {
+            S::Closed => R::Closed,
+            S::Resized { width, height } => R::Resized { width, height },
+            S::LostFocus => R::LostFocus,
+            S::GainedFocus => R::GainedFocus,
+            S::TextEntered { unicode } => R::TextEntered { unicode },
+            S::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            } => R::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            S::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            } => R::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            S::MouseWheelScrolled { wheel, delta, x, y } => {
+                R::MouseWheelScrolled { wheel, delta, x, y }
+            }
+            S::MouseButtonPressed { button, x, y } => R::MouseButtonPressed { button, x, y },
+            S::MouseButtonReleased { button, x, y } => R::MouseButtonReleased { button, x, y },
+            S::MouseMoved { x, y } => R::MouseMoved { x, y },
+            S::MouseEntered => R::MouseEntered,
+            S::MouseLeft => R::MouseLeft,
+            S::JoystickButtonPressed { joystickid, button } => {
+                R::JoystickButtonPressed { joystickid, button }
+            }
+            S::JoystickButtonReleased { joystickid, button } => {
+                R::JoystickButtonReleased { joystickid, button }
+            }
+            S::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            } => R::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            },
+            S::JoystickConnected { joystickid } => R::JoystickConnected { joystickid },
+            S::JoystickDisconnected { joystickid } => R::JoystickDisconnected { joystickid },
+            S::TouchBegan { finger, x, y } => R::TouchBegan { finger, x, y },
+            S::TouchMoved { finger, x, y } => R::TouchMoved { finger, x, y },
+            S::TouchEnded { finger, x, y } => R::TouchEnded { finger, x, y },
+            S::MouseWheelMoved | S::SensorChanged { .. } => return None,
+        })
+    }
+
+    pub fn to_window_event(self) -> SFMLEvent {
+        use RecordedEvent as R;
+        use SFMLEvent as S;
+
+        match self {
+            R::Closed => S::Closed,
+            R::Resized { width, height } => S::Resized { width, height },
+            R::LostFocus => S::LostFocus,
+            R::GainedFocus => S::GainedFocus,
+            R::TextEntered { unicode } => S::TextEntered { unicode },
+            R::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            } => S::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            R::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            } => S::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            R::MouseWheelScrolled { wheel, delta, x, y } => {
+                S::MouseWheelScrolled { wheel, delta, x, y }
+            }
+            R::MouseButtonPressed { button, x, y } => S::MouseButtonPressed { button, x, y },
+            R::MouseButtonReleased { button, x, y } => S::MouseButtonReleased { button, x, y },
+            R::MouseMoved { x, y } => S::MouseMoved { x, y },
+            R::MouseEntered => S::MouseEntered,
+            R::MouseLeft => S::MouseLeft,
+            R::JoystickButtonPressed { joystickid, button } => {
+                S::JoystickButtonPressed { joystickid, button }
+            }
+            R::JoystickButtonReleased { joystickid, button } => {
+                S::JoystickButtonReleased { joystickid, button }
+            }
+            R::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            } => S::JoystickMoved {
+                joystickid,
+                axis,
+                position,
+            },
+            R::JoystickConnected { joystickid } => S::JoystickConnected { joystickid },
+            R::JoystickDisconnected { joystickid } => S::JoystickDisconnected { joystickid },
+            R::TouchBegan { finger, x, y } => S::TouchBegan { finger, x, y },
+            R::TouchMoved { finger, x, y } => S::TouchMoved { finger, x, y },
+            R::TouchEnded { finger, x, y } => S::TouchEnded { finger, x, y },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub dt: f32,
+
+    /// Window events handled during the frame in the order they arrived, injected ones included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<RecordedEvent>,
+}
+
+/// Input, frame timing and random seed of a session, enough to replay it deterministically.
+/// `checksum` is the `tree_checksum` of the root Entity after the last frame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// Seed of `App::rng` for the session
+    #[serde(default)]
+    pub seed: u64,
+    pub frames: Vec<RecordedFrame>,
+    pub checksum: Option<u64>,
+}
+
+impl Recording {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let contents = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+}
+
+/// Accumulates frames while a session is recorded
+#[derive(Debug)]
+pub struct Recorder {
+    path: String,
+    recording: Recording,
+}
+
+impl Recorder {
+    pub fn new(path: &str, seed: u64) -> Self {
+        Self {
+            path: path.to_string(),
+            recording: Recording {
+                seed,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn begin_frame(&mut self, dt: f32) {
+        self.recording.frames.push(RecordedFrame {
+            dt,
+            events: Vec::new(),
+        });
+    }
+
+    pub fn record(&mut self, ev: &SFMLEvent) {
+        if let (Some(frame), Some(ev)) = (
+            self.recording.frames.last_mut(),
+            RecordedEvent::from_window_event(ev),
+        ) {
+            frame.events.push(ev);
+        }
+    }
+
+    /// Writes the recording along with the final checksum of the tree
+    pub fn finish(mut self, checksum: u64) -> Result<(), String> {
+        self.recording.checksum = Some(checksum);
+        self.recording.save(&self.path)
+    }
+}
+
+/// Hands out the frames of a recording in order
+#[derive(Debug)]
+pub struct Replayer {
+    recording: Recording,
+    frame: usize,
+}
+
+impl Replayer {
+    pub fn load(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            recording: Recording::load(path)?,
+            frame: 0,
+        })
+    }
+
+    pub fn next_frame(&mut self) -> Option<&RecordedFrame> {
+        let frame = self.recording.frames.get(self.frame)?;
+        self.frame += 1;
+        Some(frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames.len()
+    }
+
+    pub fn expected_checksum(&self) -> Option<u64> {
+        self.recording.checksum
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.recording.seed
+    }
+}
+
+/// FNV-1a hash of the serialized tree and the `checksum_state` of its behaviors. Entity ids are
+/// not part of either so the checksum only changes when the simulation does.
+pub fn tree_checksum(resources: &Resources, root: &mut Entity) -> u64 {
+    let desc = describe_entity(resources, root);
+    let mut states = Vec::new();
+    collect_checksum_states(root, &mut states);
+    let bytes = serde_json::to_vec(&(desc, states)).unwrap_or_default();
+
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Behavior states of the subtree in depth first order, None for Entities without one
+fn collect_checksum_states(e: &mut Entity, states: &mut Vec<Option<Value>>) {
+    states.push(e.behavior_checksum_state());
+    for child in e.children_mut() {
+        collect_checksum_states(child, states);
+    }
+}
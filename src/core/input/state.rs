@@ -0,0 +1,274 @@
+use crate::core::event::Event;
+use crate::core::input::gamepad::GamepadButton;
+
+use sfml::window::mouse::{Button, Wheel};
+use sfml::window::{Event as SFMLEvent, Key};
+use std::collections::HashSet;
+
+/// Any digital input tracked by InputState
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputButton {
+    Key(Key),
+    Mouse(Button),
+
+    /// Button from the standard layout on the gamepad with the provided id
+    Gamepad(u32, GamepadButton),
+}
+
+impl From<Key> for InputButton {
+    fn from(key: Key) -> Self {
+        InputButton::Key(key)
+    }
+}
+
+impl From<Button> for InputButton {
+    fn from(button: Button) -> Self {
+        InputButton::Mouse(button)
+    }
+}
+
+impl From<(u32, GamepadButton)> for InputButton {
+    fn from((gamepad, button): (u32, GamepadButton)) -> Self {
+        InputButton::Gamepad(gamepad, button)
+    }
+}
+
+/// Snapshot of keyboard, mouse and gamepad buttons built from the events of the current frame.
+/// `just_pressed` and `just_released` only hold for the frame in which the change happened.
+#[derive(Debug, Default)]
+pub struct InputState {
+    down: HashSet<InputButton>,
+    pressed: HashSet<InputButton>,
+    released: HashSet<InputButton>,
+
+    /// None until the first mouse event so the first move does not count as a jump from the origin
+    mouse_position: Option<(i32, i32)>,
+    mouse_delta: (i32, i32),
+
+    /// Vertical and horizontal wheel offsets accumulated this frame
+    wheel_delta: (f32, f32),
+}
+
+impl InputState {
+    pub fn is_down(&self, button: impl Into<InputButton>) -> bool {
+        self.down.contains(&button.into())
+    }
+
+    pub fn just_pressed(&self, button: impl Into<InputButton>) -> bool {
+        self.pressed.contains(&button.into())
+    }
+
+    pub fn just_released(&self, button: impl Into<InputButton>) -> bool {
+        self.released.contains(&button.into())
+    }
+
+    pub fn mouse_position(&self) -> (i32, i32) {
+        self.mouse_position.unwrap_or((0, 0))
+    }
+
+    /// How far the mouse moved this frame
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    /// Vertical and horizontal wheel offsets this frame, positive is up/left
+    pub fn wheel_delta(&self) -> (f32, f32) {
+        self.wheel_delta
+    }
+
+    /// Clears the per-frame state. Called by App before the events of a new frame are handled.
+    pub fn begin_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = (0, 0);
+        self.wheel_delta = (0., 0.);
+    }
+
+    fn press(&mut self, button: InputButton) {
+        // Key repeats arrive as extra presses while the key is already down
+        if self.down.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: InputButton) {
+        if self.down.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    /// Releases everything that is held, eg: when the window loses focus and the matching
+    /// release events would never arrive.
+    pub fn release_all(&mut self) {
+        for button in std::mem::take(&mut self.down) {
+            self.released.insert(button);
+        }
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32) {
+        if let Some((px, py)) = self.mouse_position {
+            self.mouse_delta.0 += x - px;
+            self.mouse_delta.1 += y - py;
+        }
+        self.mouse_position = Some((x, y));
+    }
+
+    pub fn handle_event(&mut self, ev: &Event) {
+        match ev {
+            Event::SFMLEvent(sev) => match *sev {
+                SFMLEvent::KeyPressed { code, .. } => self.press(code.into()),
+                SFMLEvent::KeyReleased { code, .. } => self.release(code.into()),
+                SFMLEvent::MouseButtonPressed { button, x, y } => {
+                    self.move_mouse(x, y);
+                    self.press(button.into());
+                }
+                SFMLEvent::MouseButtonReleased { button, x, y } => {
+                    self.move_mouse(x, y);
+                    self.release(button.into());
+                }
+                SFMLEvent::MouseMoved { x, y } => self.move_mouse(x, y),
+                SFMLEvent::MouseWheelScrolled { wheel, delta, .. } => match wheel {
+                    Wheel::VerticalWheel => self.wheel_delta.0 += delta,
+                    Wheel::HorizontalWheel => self.wheel_delta.1 += delta,
+                },
+                SFMLEvent::LostFocus => self.release_all(),
+                _ => {}
+            },
+            Event::GamepadButtonPressed(p) => self.press((p.gamepad, p.button).into()),
+            Event::GamepadButtonReleased(p) => self.release((p.gamepad, p.button).into()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::GamepadButtonPayload;
+
+    fn key(code: Key, down: bool) -> Event {
+        let (alt, ctrl, shift, system) = (false, false, false, false);
+        Event::SFMLEvent(match down {
+            true => SFMLEvent::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+            false => SFMLEvent::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+            },
+        })
+    }
+
+    fn mouse_moved(x: i32, y: i32) -> Event {
+        Event::SFMLEvent(SFMLEvent::MouseMoved { x, y })
+    }
+
+    #[test]
+    fn presses_and_releases_only_last_one_frame() {
+        let mut input = InputState::default();
+        input.handle_event(&key(Key::Space, true));
+        assert!(input.is_down(Key::Space));
+        assert!(input.just_pressed(Key::Space));
+
+        input.begin_frame();
+        assert!(input.is_down(Key::Space));
+        assert!(!input.just_pressed(Key::Space));
+
+        input.handle_event(&key(Key::Space, false));
+        assert!(!input.is_down(Key::Space));
+        assert!(input.just_released(Key::Space));
+
+        input.begin_frame();
+        assert!(!input.just_released(Key::Space));
+    }
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let mut input = InputState::default();
+        input.handle_event(&key(Key::A, true));
+        input.begin_frame();
+        input.handle_event(&key(Key::A, true));
+        assert!(!input.just_pressed(Key::A));
+    }
+
+    #[test]
+    fn tap_within_a_frame_is_both_pressed_and_released() {
+        let mut input = InputState::default();
+        input.handle_event(&key(Key::A, true));
+        input.handle_event(&key(Key::A, false));
+        assert!(input.just_pressed(Key::A));
+        assert!(input.just_released(Key::A));
+        assert!(!input.is_down(Key::A));
+    }
+
+    #[test]
+    fn losing_focus_releases_held_buttons() {
+        let mut input = InputState::default();
+        input.handle_event(&key(Key::W, true));
+        input.handle_event(&Event::SFMLEvent(SFMLEvent::MouseButtonPressed {
+            button: Button::Left,
+            x: 0,
+            y: 0,
+        }));
+        input.begin_frame();
+
+        input.handle_event(&Event::SFMLEvent(SFMLEvent::LostFocus));
+        assert!(!input.is_down(Key::W));
+        assert!(!input.is_down(Button::Left));
+        assert!(input.just_released(Key::W));
+        assert!(input.just_released(Button::Left));
+    }
+
+    #[test]
+    fn mouse_delta_accumulates_per_frame_from_the_first_position() {
+        let mut input = InputState::default();
+
+        // The first event only establishes the position
+        input.handle_event(&mouse_moved(100, 100));
+        assert_eq!(input.mouse_delta(), (0, 0));
+
+        input.handle_event(&mouse_moved(110, 95));
+        input.handle_event(&mouse_moved(115, 90));
+        assert_eq!(input.mouse_delta(), (15, -10));
+        assert_eq!(input.mouse_position(), (115, 90));
+
+        input.begin_frame();
+        assert_eq!(input.mouse_delta(), (0, 0));
+        assert_eq!(input.mouse_position(), (115, 90));
+    }
+
+    #[test]
+    fn wheel_delta_accumulates_per_frame() {
+        let mut input = InputState::default();
+        for delta in [1., 2.] {
+            input.handle_event(&Event::SFMLEvent(SFMLEvent::MouseWheelScrolled {
+                wheel: Wheel::VerticalWheel,
+                delta,
+                x: 0,
+                y: 0,
+            }));
+        }
+        assert_eq!(input.wheel_delta(), (3., 0.));
+
+        input.begin_frame();
+        assert_eq!(input.wheel_delta(), (0., 0.));
+    }
+
+    #[test]
+    fn gamepad_buttons_are_tracked_per_gamepad() {
+        let mut input = InputState::default();
+        input.handle_event(&Event::GamepadButtonPressed(GamepadButtonPayload {
+            gamepad: 1,
+            button: GamepadButton::South,
+        }));
+        assert!(input.is_down((1, GamepadButton::South)));
+        assert!(!input.is_down((0, GamepadButton::South)));
+    }
+}
@@ -1,6 +1,13 @@
 pub mod audio;
 pub mod component;
+pub mod drag;
 pub mod entity;
 pub mod event;
+pub mod event_queue;
+pub mod focus;
+pub mod input;
+pub mod pointer;
 pub mod renderer;
 pub mod resource;
+pub mod scene;
+pub mod scheduler;
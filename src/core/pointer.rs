@@ -0,0 +1,252 @@
+use crate::core::entity::pick::PointerHit;
+use crate::core::entity::EntityId;
+use crate::core::event::{Event, PointerPayload};
+
+use sfml::window::mouse::Button;
+use sfml::window::Event as SFMLEvent;
+use std::collections::HashMap;
+
+/// Screen space position of a mouse event which needs a hit test
+pub fn pointer_position(ev: &SFMLEvent) -> Option<(f32, f32)> {
+    match *ev {
+        SFMLEvent::MouseMoved { x, y }
+        | SFMLEvent::MouseButtonPressed { x, y, .. }
+        | SFMLEvent::MouseButtonReleased { x, y, .. } => Some((x as f32, y as f32)),
+        _ => None,
+    }
+}
+
+/// Tracks the Pickable Entity under the mouse and turns window mouse events into pointer events
+/// for the topmost hit Entity
+#[derive(Debug, Default)]
+pub struct Pointer {
+    hovered: Option<PointerHit>,
+
+    /// Screen space position of the latest mouse event
+    position: (f32, f32),
+
+    /// Entity each held button was pressed over, a release over the same Entity is a click
+    pressed: HashMap<Button, EntityId>,
+}
+
+impl Pointer {
+    pub fn hovered(&self) -> Option<EntityId> {
+        self.hovered.map(|h| h.id)
+    }
+
+    /// Forgets the Entity a button was pressed over so its release is not a click, eg: when the
+    /// press turned into a drag
+    pub fn cancel_click(&mut self, button: Button) {
+        self.pressed.remove(&button);
+    }
+
+    /// Pointer events produced by a window event, paired with the Entity they are delivered to.
+    /// `hit` is the result of hit testing at the event's `pointer_position`.
+    pub fn handle_event(
+        &mut self,
+        ev: &SFMLEvent,
+        hit: Option<PointerHit>,
+    ) -> Vec<(EntityId, Event)> {
+        let mut events = Vec::new();
+
+        let (x, y) = match (ev, pointer_position(ev)) {
+            (_, Some(position)) => {
+                self.position = position;
+                position
+            }
+            (SFMLEvent::MouseLeft, _) => {
+                if let Some(old) = self.hovered.take() {
+                    events.push((
+                        old.id,
+                        Event::PointerLeave(payload(old, self.position, None)),
+                    ));
+                }
+                return events;
+            }
+            _ => return events,
+        };
+
+        // Hover changes come first so Enter always precedes presses on the new Entity
+        if self.hovered() != hit.map(|h| h.id) {
+            if let Some(old) = self.hovered {
+                events.push((old.id, Event::PointerLeave(payload(old, (x, y), None))));
+            }
+            if let Some(new) = hit {
+                events.push((new.id, Event::PointerEnter(payload(new, (x, y), None))));
+            }
+        }
+        self.hovered = hit;
+
+        match *ev {
+            SFMLEvent::MouseMoved { .. } => {
+                if let Some(hit) = hit {
+                    events.push((hit.id, Event::PointerHover(payload(hit, (x, y), None))));
+                }
+            }
+            SFMLEvent::MouseButtonPressed { button, .. } => match hit {
+                Some(hit) => {
+                    self.pressed.insert(button, hit.id);
+                    let p = payload(hit, (x, y), Some(button));
+                    events.push((hit.id, Event::PointerPressed(p)));
+                }
+                None => {
+                    self.pressed.remove(&button);
+                }
+            },
+            SFMLEvent::MouseButtonReleased { button, .. } => {
+                let pressed_on = self.pressed.remove(&button);
+                if let Some(hit) = hit {
+                    let p = payload(hit, (x, y), Some(button));
+                    events.push((hit.id, Event::PointerReleased(p)));
+                    if pressed_on == Some(hit.id) {
+                        events.push((hit.id, Event::PointerClick(p)));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        events
+    }
+}
+
+fn payload(hit: PointerHit, (x, y): (f32, f32), button: Option<Button>) -> PointerPayload {
+    PointerPayload {
+        entity: hit.id,
+        x,
+        y,
+        local: hit.local,
+        button,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::entity::Entity;
+
+    fn hit(id: EntityId) -> Option<PointerHit> {
+        Some(PointerHit {
+            id,
+            local: (1., 2.),
+        })
+    }
+
+    fn moved(x: i32, y: i32) -> SFMLEvent {
+        SFMLEvent::MouseMoved { x, y }
+    }
+
+    fn pressed() -> SFMLEvent {
+        SFMLEvent::MouseButtonPressed {
+            button: Button::Left,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    fn released() -> SFMLEvent {
+        SFMLEvent::MouseButtonReleased {
+            button: Button::Left,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Kind of each pointer event with the Entity it is delivered to
+    fn kinds(events: &[(EntityId, Event)]) -> Vec<(&'static str, EntityId)> {
+        events
+            .iter()
+            .map(|(id, ev)| {
+                let kind = match ev {
+                    Event::PointerEnter(_) => "enter",
+                    Event::PointerLeave(_) => "leave",
+                    Event::PointerHover(_) => "hover",
+                    Event::PointerPressed(_) => "pressed",
+                    Event::PointerReleased(_) => "released",
+                    Event::PointerClick(_) => "click",
+                    _ => "other",
+                };
+                (kind, *id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn leave_comes_before_enter() {
+        let (a, b) = (Entity::new("a").id(), Entity::new("b").id());
+        let mut pointer = Pointer::default();
+
+        let events = pointer.handle_event(&moved(1, 1), hit(a));
+        assert_eq!(kinds(&events), [("enter", a), ("hover", a)]);
+
+        let events = pointer.handle_event(&moved(2, 2), hit(b));
+        assert_eq!(kinds(&events), [("leave", a), ("enter", b), ("hover", b)]);
+
+        let events = pointer.handle_event(&moved(3, 3), None);
+        assert_eq!(kinds(&events), [("leave", b)]);
+        assert_eq!(pointer.hovered(), None);
+    }
+
+    #[test]
+    fn leaving_the_window_leaves_the_hovered_entity() {
+        let a = Entity::new("a").id();
+        let mut pointer = Pointer::default();
+        pointer.handle_event(&moved(1, 1), hit(a));
+
+        let events = pointer.handle_event(&SFMLEvent::MouseLeft, None);
+        assert_eq!(kinds(&events), [("leave", a)]);
+        assert!(pointer.handle_event(&SFMLEvent::MouseLeft, None).is_empty());
+    }
+
+    #[test]
+    fn payload_carries_the_screen_and_local_position() {
+        let a = Entity::new("a").id();
+        let mut pointer = Pointer::default();
+
+        let events = pointer.handle_event(&moved(10, 20), hit(a));
+        match &events[1] {
+            (_, Event::PointerHover(p)) => {
+                assert_eq!((p.entity, p.x, p.y, p.local), (a, 10., 20., (1., 2.)));
+                assert_eq!(p.button, None);
+            }
+            other => panic!("expected a hover, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn click_needs_press_and_release_on_the_same_entity() {
+        let (a, b) = (Entity::new("a").id(), Entity::new("b").id());
+        let mut pointer = Pointer::default();
+
+        pointer.handle_event(&pressed(), hit(a));
+        let events = pointer.handle_event(&released(), hit(a));
+        assert_eq!(kinds(&events), [("released", a), ("click", a)]);
+        match &events[0] {
+            (_, Event::PointerReleased(p)) => assert_eq!(p.button, Some(Button::Left)),
+            other => panic!("expected a release, got {:?}", other),
+        }
+
+        pointer.handle_event(&pressed(), hit(a));
+        let events = pointer.handle_event(&released(), hit(b));
+        assert_eq!(
+            kinds(&events),
+            [("leave", a), ("enter", b), ("released", b)]
+        );
+
+        pointer.handle_event(&pressed(), None);
+        let events = pointer.handle_event(&released(), hit(b));
+        assert_eq!(kinds(&events), [("enter", b), ("released", b)]);
+    }
+
+    #[test]
+    fn cancelled_clicks_only_release() {
+        let a = Entity::new("a").id();
+        let mut pointer = Pointer::default();
+
+        pointer.handle_event(&pressed(), hit(a));
+        pointer.cancel_click(Button::Left);
+        let events = pointer.handle_event(&released(), hit(a));
+        assert_eq!(kinds(&events), [("released", a)]);
+    }
+}
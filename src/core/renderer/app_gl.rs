@@ -2,7 +2,9 @@ use core::ffi::c_void;
 use gl::types::GLint;
 use itertools::Itertools;
 use sfml::graphics::Image;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ffi::CString;
 use std::fs::File;
 use std::io::Read;
 use std::rc::Rc;
@@ -12,15 +14,9 @@ extern crate nalgebra_glm as glm;
 
 use std::convert::TryInto;
 
+use crate::core::renderer::batch::create_batch_buffers;
 use crate::util::clamp;
-
-#[derive(Debug, Default, Clone, Copy)]
-pub struct GLProgram {
-    pub id: u32,
-    pub mvp_loc: i32,
-    pub color_loc: i32,
-    pub uv_rect_loc: i32,
-}
+use crate::util::rect::Rect;
 
 #[derive(Debug, Default)]
 pub struct AppGL {
@@ -29,8 +25,18 @@ pub struct AppGL {
     pub vbo: u32,
     pub ebo: u32,
 
-    pub image_program: GLProgram,
-    pub text_program: GLProgram,
+    /// Buffers of the sprite batch, see `renderer::batch`
+    pub batch_vao: u32,
+    pub batch_vbo: u32,
+    pub batch_ebo: u32,
+
+    /// Programs quads without a Material are drawn with, see `renderer::material` for others
+    pub image_program: u32,
+    pub text_program: u32,
+
+    /// Samplers for `ImageRenderType`, bound in place of a texture's own filtering
+    pub nearest_sampler: u32,
+    pub linear_sampler: u32,
 }
 
 #[repr(C)]
@@ -325,7 +331,186 @@ pub fn render_text_to_texture(bundle: RenderTextBundle) -> Result<Texture, &str>
     }
 }
 
+/// Coverage of rendered text as white RGBA pixels, for packing text into an atlas
+pub fn render_text_to_pixels(bundle: RenderTextBundle) -> (u32, u32, Vec<u8>) {
+    let mut texture_data = TextTextureData::default();
+    sw_render_text_to_buffer(bundle, &mut texture_data);
+
+    let pixels = texture_data
+        .data
+        .iter()
+        .flat_map(|coverage| [255, 255, 255, *coverage])
+        .collect();
+    (
+        texture_data.width as u32,
+        texture_data.height as u32,
+        pixels,
+    )
+}
+
+/// Decodes an image file into tightly packed RGBA pixels without uploading it
+pub fn load_image_pixels_from_disk(path: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    let mut img_bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut img_bytes))
+        .map_err(|err| err.to_string())?;
+
+    match Image::from_memory(&img_bytes) {
+        Some(img_data) => {
+            let size = img_data.size();
+            Ok((size.x, size.y, img_data.pixel_data().to_vec()))
+        }
+        None => {
+            println!("Bad Image for path: {:?}", path);
+            Err("Bad Image".to_string())
+        }
+    }
+}
+
+/// Creates an empty RGBA texture without mipmaps, eg: an atlas page. Only its base level is
+/// ever sampled so regions packed next to each other do not bleed at lower levels.
+pub fn create_texture(width: u32, height: u32) -> Result<Texture, String> {
+    unsafe {
+        let mut id: u32 = 0;
+        GenTextures(1, &mut id);
+
+        if id == 0 {
+            return Err("Failed to generate texture".to_string());
+        }
+
+        BindTexture(TEXTURE_2D, id);
+        TexParameteri(
+            TEXTURE_2D,
+            TEXTURE_WRAP_S,
+            CLAMP_TO_EDGE.try_into().unwrap(),
+        );
+        TexParameteri(
+            TEXTURE_2D,
+            TEXTURE_WRAP_T,
+            CLAMP_TO_EDGE.try_into().unwrap(),
+        );
+        TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR.try_into().unwrap());
+        TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR.try_into().unwrap());
+        TexParameteri(TEXTURE_2D, TEXTURE_MAX_LEVEL, 0);
+        TexImage2D(
+            TEXTURE_2D,
+            0,
+            RGBA.try_into().unwrap(),
+            width as GLint,
+            height as GLint,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            std::ptr::null::<c_void>(),
+        );
+        BindTexture(TEXTURE_2D, 0);
+
+        Ok(Texture {
+            texture_id: id,
+            width,
+            height,
+        })
+    }
+}
+
+/// Copies tightly packed RGBA pixels into `rect` of a texture
+pub fn upload_sub_image(texture: &Texture, rect: &Rect, pixels: &[u8]) {
+    forget_texture_pixels(texture.texture_id);
+    unsafe {
+        BindTexture(TEXTURE_2D, texture.texture_id);
+        PixelStorei(UNPACK_ALIGNMENT, 1);
+        TexSubImage2D(
+            TEXTURE_2D,
+            0,
+            rect.x as GLint,
+            rect.y as GLint,
+            rect.w as GLint,
+            rect.h as GLint,
+            RGBA,
+            UNSIGNED_BYTE,
+            pixels.as_ptr() as *const c_void,
+        );
+        BindTexture(TEXTURE_2D, 0);
+    }
+}
+
+/// Framebuffer rendering into a new RGBA texture of the given size
+pub fn create_framebuffer(width: u32, height: u32) -> Result<(u32, Texture), String> {
+    let texture = create_texture(width, height)?;
+
+    unsafe {
+        let mut fbo: u32 = 0;
+        GenFramebuffers(1, &mut fbo);
+        BindFramebuffer(FRAMEBUFFER, fbo);
+        FramebufferTexture2D(
+            FRAMEBUFFER,
+            COLOR_ATTACHMENT0,
+            TEXTURE_2D,
+            texture.texture_id,
+            0,
+        );
+        let status = CheckFramebufferStatus(FRAMEBUFFER);
+        BindFramebuffer(FRAMEBUFFER, 0);
+
+        if status != FRAMEBUFFER_COMPLETE {
+            DeleteFramebuffers(1, &fbo);
+            release_texture(texture.texture_id);
+            return Err(format!("Incomplete framebuffer: {}", status));
+        }
+
+        Ok((fbo, texture))
+    }
+}
+
+pub fn release_framebuffer(fbo: u32) {
+    unsafe {
+        DeleteFramebuffers(1, &fbo);
+    }
+}
+
+thread_local! {
+    /// CPU copies of textures read back for alpha picking, keyed by texture id. Entries are
+    /// dropped as soon as a texture changes or is released, the driver may reuse its id.
+    static PIXEL_CACHE: RefCell<HashMap<u32, Rc<Vec<u8>>>> = RefCell::new(HashMap::new());
+}
+
+/// Pixels of a texture as returned by `read_texture_pixels`, read back once until it changes
+pub fn cached_texture_pixels(texture: &Texture) -> Rc<Vec<u8>> {
+    PIXEL_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .entry(texture.texture_id)
+            .or_insert_with(|| Rc::new(read_texture_pixels(texture)))
+            .clone()
+    })
+}
+
+/// Drops the cached copy of a texture whose pixels changed on the GPU
+pub fn forget_texture_pixels(texture_id: u32) {
+    PIXEL_CACHE.with(|cache| cache.borrow_mut().remove(&texture_id));
+}
+
+/// Reads a texture back from the GPU as tightly packed RGBA rows. Single channel textures read
+/// back with their value in the red channel.
+pub fn read_texture_pixels(texture: &Texture) -> Vec<u8> {
+    let mut pixels = vec![0u8; (texture.width * texture.height * 4) as usize];
+    unsafe {
+        BindTexture(TEXTURE_2D, texture.texture_id);
+        PixelStorei(PACK_ALIGNMENT, 1);
+        GetTexImage(
+            TEXTURE_2D,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut c_void,
+        );
+        BindTexture(TEXTURE_2D, 0);
+    }
+    pixels
+}
+
 pub fn release_texture(texture_id: u32) {
+    forget_texture_pixels(texture_id);
     unsafe {
         DeleteTextures(1, &texture_id);
     }
@@ -339,6 +524,19 @@ fn gen_buffer() -> u32 {
     }
 }
 
+/// Sampler clamping to the edge with the given min and mag filters
+fn create_sampler(min_filter: u32, mag_filter: u32) -> u32 {
+    unsafe {
+        let mut id: u32 = 0;
+        GenSamplers(1, &mut id);
+        SamplerParameteri(id, TEXTURE_WRAP_S, CLAMP_TO_EDGE.try_into().unwrap());
+        SamplerParameteri(id, TEXTURE_WRAP_T, CLAMP_TO_EDGE.try_into().unwrap());
+        SamplerParameteri(id, TEXTURE_MIN_FILTER, min_filter.try_into().unwrap());
+        SamplerParameteri(id, TEXTURE_MAG_FILTER, mag_filter.try_into().unwrap());
+        id
+    }
+}
+
 fn gen_vertex_buffer() -> u32 {
     unsafe {
         let mut id: u32 = 0;
@@ -352,7 +550,13 @@ fn create_shader(shader_type: u32, shader_source_location: &str) -> Result<u32,
         let id = CreateShader(shader_type);
 
         if id != 0 {
-            let mut source = File::open(shader_source_location).unwrap();
+            let mut source = match File::open(shader_source_location) {
+                Ok(source) => source,
+                Err(_) => {
+                    DeleteShader(id);
+                    return Err("Failed to open shader");
+                }
+            };
             let mut contents = Vec::new();
             source
                 .read_to_end(&mut contents)
@@ -382,6 +586,7 @@ fn create_shader(shader_type: u32, shader_source_location: &str) -> Result<u32,
                     "Failed to compile shader: {}, error_status: {}, log: {:?}",
                     shader_source_location, compile_status, error_string
                 );
+                DeleteShader(id);
             } else {
                 return Ok(id);
             }
@@ -390,9 +595,19 @@ fn create_shader(shader_type: u32, shader_source_location: &str) -> Result<u32,
     }
 }
 
-fn create_and_link_program(vertex_shader_source: &str, fragment_shader_source: &str) -> u32 {
-    let vertex_shader = create_shader(VERTEX_SHADER, vertex_shader_source).unwrap();
-    let fragment_shader = create_shader(FRAGMENT_SHADER, fragment_shader_source).unwrap();
+/// Compiles and links a program from vertex and fragment shader files
+pub fn create_and_link_program(
+    vertex_shader_source: &str,
+    fragment_shader_source: &str,
+) -> Result<u32, String> {
+    let vertex_shader = create_shader(VERTEX_SHADER, vertex_shader_source)?;
+    let fragment_shader = match create_shader(FRAGMENT_SHADER, fragment_shader_source) {
+        Ok(shader) => shader,
+        Err(err) => {
+            unsafe { DeleteShader(vertex_shader) };
+            return Err(err.to_string());
+        }
+    };
 
     unsafe {
         let id = CreateProgram();
@@ -423,7 +638,26 @@ fn create_and_link_program(vertex_shader_source: &str, fragment_shader_source: &
         DeleteShader(vertex_shader);
         DeleteShader(fragment_shader);
 
-        id
+        if link_status == 0 {
+            DeleteProgram(id);
+            return Err("Failed to link program".to_string());
+        }
+
+        Ok(id)
+    }
+}
+
+pub fn release_program(program: u32) {
+    unsafe {
+        DeleteProgram(program);
+    }
+}
+
+/// Location of a uniform in a program, -1 when the program does not use it
+pub fn uniform_location(program: u32, name: &str) -> i32 {
+    match CString::new(name) {
+        Ok(name) => unsafe { GetUniformLocation(program, name.as_ptr()) },
+        Err(_) => -1,
     }
 }
 
@@ -527,52 +761,48 @@ impl AppGL {
             self.ebo = gen_buffer();
             report_error("gen ebo");
 
-            self.image_program.id =
-                create_and_link_program("res/glsl/imagev.glsl", "res/glsl/image.glsl");
+            self.image_program =
+                create_and_link_program("res/glsl/imagev.glsl", "res/glsl/image.glsl").unwrap();
             report_error("create_and_link_program image");
 
-            self.text_program.id =
-                create_and_link_program("res/glsl/textv.glsl", "res/glsl/text.glsl");
+            self.text_program =
+                create_and_link_program("res/glsl/imagev.glsl", "res/glsl/text.glsl").unwrap();
             report_error("text");
 
             upload_buffer_data(self.vao, self.vbo, self.ebo);
             report_error("upload buffer data");
 
-            let mvp_name = "mvp\0".as_bytes();
-            let color_name = "color\0".as_bytes();
-            let uv_rect = "uv_rect\0".as_bytes();
+            self.batch_vao = gen_vertex_buffer();
+            self.batch_vbo = gen_buffer();
+            self.batch_ebo = gen_buffer();
+            create_batch_buffers(self.batch_vao, self.batch_vbo, self.batch_ebo);
+            report_error("create batch buffers");
 
-            self.image_program.mvp_loc =
-                GetUniformLocation(self.image_program.id, mvp_name.as_ptr() as *const i8);
-            report_error("image mvp");
-
-            self.image_program.color_loc =
-                GetUniformLocation(self.image_program.id, color_name.as_ptr() as *const i8);
-            report_error("image color");
-
-            self.image_program.uv_rect_loc =
-                GetUniformLocation(self.image_program.id, uv_rect.as_ptr() as *const i8);
-            report_error("image uv_rect");
-
-            self.text_program.mvp_loc =
-                GetUniformLocation(self.text_program.id, mvp_name.as_ptr() as *const i8);
-            report_error("text mvp");
-
-            self.text_program.color_loc =
-                GetUniformLocation(self.text_program.id, color_name.as_ptr() as *const i8);
-            report_error("text color");
+            self.nearest_sampler = create_sampler(NEAREST_MIPMAP_NEAREST, NEAREST);
+            self.linear_sampler = create_sampler(LINEAR_MIPMAP_LINEAR, LINEAR);
+            report_error("create samplers");
         }
     }
 }
 
 impl Drop for AppGL {
     fn drop(&mut self) {
+        // Headless Apps never load GL
+        if !self.has_init {
+            return;
+        }
+
         unsafe {
+            DeleteSamplers(1, &self.nearest_sampler);
+            DeleteSamplers(1, &self.linear_sampler);
+            DeleteBuffers(1, &self.batch_ebo);
+            DeleteBuffers(1, &self.batch_vbo);
+            DeleteVertexArrays(1, &self.batch_vao);
             DeleteBuffers(1, &self.ebo);
             DeleteBuffers(1, &self.vbo);
             DeleteVertexArrays(1, &self.vao);
-            DeleteProgram(self.image_program.id);
-            DeleteProgram(self.text_program.id);
+            DeleteProgram(self.image_program);
+            DeleteProgram(self.text_program);
             gl_loader::end_gl();
         }
     }
@@ -0,0 +1,220 @@
+use crate::core::renderer::app_gl::{create_texture, upload_sub_image, Texture};
+use crate::util::rect::Rect;
+
+/// Size of a new atlas page in pixels
+pub static ATLAS_PAGE_SIZE: u32 = 2048;
+
+/// Largest width or height packed into an atlas, bigger images keep their own texture
+pub static MAX_ATLAS_REGION: u32 = 512;
+
+/// Empty pixels kept between regions so filtering does not bleed into neighbours
+static ATLAS_PADDING: u32 = 2;
+
+/// Row of a RectPacker, regions are placed left to right along it
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// Shelf packer for rectangles of mixed sizes. Each region goes on the shortest shelf it fits
+/// on, a new shelf is opened below the last one otherwise.
+#[derive(Debug)]
+pub struct RectPacker {
+    width: u32,
+    height: u32,
+    padding: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl RectPacker {
+    pub fn new(width: u32, height: u32, padding: u32) -> Self {
+        Self {
+            width,
+            height,
+            padding,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Top left corner of the space reserved for a `width` by `height` region, None when full
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (w, h) = (width + self.padding, height + self.padding);
+        let page_width = self.width;
+
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|s| s.height >= h && s.x + w <= page_width)
+            .min_by_key(|s| s.height);
+        if let Some(shelf) = shelf {
+            let position = (shelf.x, shelf.y);
+            shelf.x += w;
+            return Some(position);
+        }
+
+        let y = self.shelves.last().map_or(0, |s| s.y + s.height);
+        if w > self.width || y + h > self.height {
+            return None;
+        }
+
+        self.shelves.push(Shelf { y, height: h, x: w });
+        Some((0, y))
+    }
+}
+
+/// Section of a texture holding a single image. `rect` is in pixels of `texture`.
+#[derive(Debug, Clone, Copy)]
+pub struct AtlasRegion {
+    pub texture: Texture,
+    pub rect: Rect,
+}
+
+impl AtlasRegion {
+    /// Region covering all of a texture
+    pub fn whole(texture: Texture) -> Self {
+        Self {
+            texture,
+            rect: Rect {
+                x: 0.,
+                y: 0.,
+                w: texture.width as f32,
+                h: texture.height as f32,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AtlasPage {
+    texture: Texture,
+    packer: RectPacker,
+}
+
+/// RGBA textures shared by many small images, pages are created as they fill up
+#[derive(Debug, Default)]
+pub struct TextureAtlas {
+    pages: Vec<AtlasPage>,
+}
+
+impl TextureAtlas {
+    pub fn fits(width: u32, height: u32) -> bool {
+        width <= MAX_ATLAS_REGION && height <= MAX_ATLAS_REGION
+    }
+
+    /// Copies tightly packed RGBA `pixels` into the first page with room for them
+    pub fn insert(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<AtlasRegion, String> {
+        if width == 0 || height == 0 {
+            return Err("Empty atlas region".to_string());
+        } else if !Self::fits(width, height) {
+            return Err(format!("{}x{} is too large for the atlas", width, height));
+        }
+
+        let placed = self
+            .pages
+            .iter_mut()
+            .find_map(|page| Some((page.texture, page.packer.insert(width, height)?)));
+
+        let (texture, (x, y)) = match placed {
+            Some(placed) => placed,
+            None => {
+                let mut page = AtlasPage {
+                    texture: create_texture(ATLAS_PAGE_SIZE, ATLAS_PAGE_SIZE)?,
+                    packer: RectPacker::new(ATLAS_PAGE_SIZE, ATLAS_PAGE_SIZE, ATLAS_PADDING),
+                };
+                let position = page
+                    .packer
+                    .insert(width, height)
+                    .ok_or("Failed to pack atlas region")?;
+                let texture = page.texture;
+                self.pages.push(page);
+                (texture, position)
+            }
+        };
+
+        let rect = Rect {
+            x: x as f32,
+            y: y as f32,
+            w: width as f32,
+            h: height as f32,
+        };
+        upload_sub_image(&texture, &rect, pixels);
+
+        Ok(AtlasRegion { texture, rect })
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = &Texture> {
+        self.pages.iter().map(|page| &page.texture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_a_shelf_left_to_right() {
+        let mut packer = RectPacker::new(100, 100, 0);
+        assert_eq!(packer.insert(30, 20), Some((0, 0)));
+        assert_eq!(packer.insert(30, 10), Some((30, 0)));
+        assert_eq!(packer.insert(40, 20), Some((60, 0)));
+
+        // The first shelf is full so a second one opens below it
+        assert_eq!(packer.insert(10, 10), Some((0, 20)));
+    }
+
+    #[test]
+    fn prefers_the_shortest_shelf_that_fits() {
+        let mut packer = RectPacker::new(100, 100, 0);
+        assert_eq!(packer.insert(10, 10), Some((0, 0)));
+        assert_eq!(packer.insert(10, 40), Some((0, 10)));
+
+        // Both shelves have room, the short one wastes less space
+        assert_eq!(packer.insert(10, 8), Some((10, 0)));
+        assert_eq!(packer.insert(10, 20), Some((10, 10)));
+    }
+
+    #[test]
+    fn full_page_rejects_regions() {
+        // None is what makes TextureAtlas open a new page
+        let mut packer = RectPacker::new(64, 64, 0);
+        for row in 0..4 {
+            for column in 0..4 {
+                assert_eq!(packer.insert(16, 16), Some((column * 16, row * 16)));
+            }
+        }
+        assert_eq!(packer.insert(16, 16), None);
+        assert_eq!(packer.insert(1, 1), None);
+    }
+
+    #[test]
+    fn rejects_oversized_regions() {
+        let mut packer = RectPacker::new(64, 64, 0);
+        assert_eq!(packer.insert(65, 10), None);
+        assert_eq!(packer.insert(10, 65), None);
+        assert_eq!(packer.insert(64, 64), Some((0, 0)));
+
+        assert!(TextureAtlas::fits(MAX_ATLAS_REGION, MAX_ATLAS_REGION));
+        assert!(!TextureAtlas::fits(MAX_ATLAS_REGION + 1, 1));
+        assert!(!TextureAtlas::fits(1, MAX_ATLAS_REGION + 1));
+    }
+
+    #[test]
+    fn keeps_padding_between_regions() {
+        let mut packer = RectPacker::new(100, 100, 2);
+        assert_eq!(packer.insert(10, 10), Some((0, 0)));
+        assert_eq!(packer.insert(10, 10), Some((12, 0)));
+        assert_eq!(packer.insert(98, 10), Some((0, 12)));
+
+        // Padding counts towards the page size
+        let mut packer = RectPacker::new(20, 20, 2);
+        assert_eq!(packer.insert(19, 19), None);
+        assert_eq!(packer.insert(18, 18), Some((0, 0)));
+    }
+}
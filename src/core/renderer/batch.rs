@@ -0,0 +1,227 @@
+use crate::core::component::image::ImageRenderType;
+use crate::core::renderer::app_gl::{AppGL, Texture};
+use crate::core::renderer::material::Material;
+use crate::util::color::Color;
+use crate::util::rect::Rect;
+
+use core::ffi::c_void;
+use std::convert::TryInto;
+
+use gl::*;
+use serde::{Deserialize, Serialize};
+
+extern crate nalgebra_glm as glm;
+
+/// Quads drawn by a single flush before the vertex buffer is full
+pub static MAX_BATCH_QUADS: usize = 4096;
+
+/// How a quad is blended with what is already on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Alpha,
+    Additive,
+}
+
+/// State shared by every quad in a batch, a quad with a different key starts a new batch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchKey {
+    pub texture_id: u32,
+    pub program: u32,
+    pub blend: BlendMode,
+    pub filter: Option<ImageRenderType>,
+
+    /// Id of the Material the quad is drawn with, 0 for none
+    pub material: u64,
+}
+
+/// Vertex layout of the sprite program, positions are already in clip space
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BatchVertex {
+    pos: [f32; 3],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+/// A textured quad, `mvp` maps the unit quad centered on the origin to clip space
+#[derive(Debug, Clone, Copy)]
+pub struct BatchQuad {
+    pub mvp: glm::TMat4<f32>,
+
+    /// Normalized section of the texture to sample
+    pub uv_rect: Rect,
+    pub color: Color,
+    pub alpha: f32,
+}
+
+/// Collects quads in render order while they share a BatchKey and draws them with a single
+/// DrawElements. Anything which draws outside of the batch must `flush` it first so z-order is
+/// kept.
+#[derive(Debug, Default)]
+pub struct SpriteBatch {
+    key: Option<BatchKey>,
+
+    /// Uniforms of the pending quads' Material as they were when the batch started
+    material: Option<Material>,
+    vertices: Vec<BatchVertex>,
+
+    /// Seconds since the renderer started, for time uniforms
+    pub time: f32,
+}
+
+// Unit quad corners and their texture coordinates, matching the shared quad buffer
+static CORNERS: [([f32; 2], [f32; 2]); 4] = [
+    ([0.5, 0.5], [1., 0.]),
+    ([0.5, -0.5], [1., 1.]),
+    ([-0.5, -0.5], [0., 1.]),
+    ([-0.5, 0.5], [0., 0.]),
+];
+
+impl SpriteBatch {
+    /// Queues a quad, drawing the pending ones first when `key` differs or the buffer is full.
+    /// `material` must be the one `key` was made with.
+    pub fn push(
+        &mut self,
+        gl: &AppGL,
+        key: BatchKey,
+        material: Option<&Material>,
+        quad: &BatchQuad,
+    ) {
+        if self.key != Some(key) || self.vertices.len() >= MAX_BATCH_QUADS * 4 {
+            self.flush(gl);
+            self.key = Some(key);
+            self.material = material.cloned();
+        }
+
+        let color = [quad.color.r, quad.color.g, quad.color.b, quad.alpha];
+        for (pos, uv) in CORNERS {
+            let clip = quad.mvp * glm::vec4(pos[0], pos[1], 0., 1.);
+            self.vertices.push(BatchVertex {
+                pos: [clip.x, clip.y, clip.z],
+                uv: [
+                    uv[0] * quad.uv_rect.w + quad.uv_rect.x,
+                    uv[1] * quad.uv_rect.h + quad.uv_rect.y,
+                ],
+                color,
+            });
+        }
+    }
+
+    /// Draws the pending quads
+    pub fn flush(&mut self, gl: &AppGL) {
+        let material = self.material.take();
+        let key = match self.key.take() {
+            Some(key) if !self.vertices.is_empty() => key,
+            _ => return,
+        };
+
+        let quads = self.vertices.len() / 4;
+        unsafe {
+            Enable(BLEND);
+            match key.blend {
+                BlendMode::Alpha => BlendFunc(SRC_ALPHA, ONE_MINUS_SRC_ALPHA),
+                BlendMode::Additive => BlendFunc(SRC_ALPHA, ONE),
+            }
+
+            BindVertexArray(gl.batch_vao);
+            BindBuffer(ARRAY_BUFFER, gl.batch_vbo);
+            BufferSubData(
+                ARRAY_BUFFER,
+                0,
+                std::mem::size_of_val(self.vertices.as_slice())
+                    .try_into()
+                    .unwrap(),
+                self.vertices.as_ptr() as *const c_void,
+            );
+            BindBuffer(ELEMENT_ARRAY_BUFFER, gl.batch_ebo);
+            UseProgram(key.program);
+            BindTexture(TEXTURE_2D, key.texture_id);
+
+            // Samplers leave the texture alone, it may be an atlas page shared with other images
+            let sampler = match key.filter {
+                Some(ImageRenderType::Nearest) => gl.nearest_sampler,
+                Some(ImageRenderType::Linear) => gl.linear_sampler,
+                None => 0,
+            };
+            BindSampler(0, sampler);
+
+            if let Some(material) = &material {
+                material.apply(self.time);
+            }
+
+            DrawElements(
+                TRIANGLES,
+                (quads * 6) as i32,
+                UNSIGNED_INT,
+                std::ptr::null::<c_void>(),
+            );
+            BindSampler(0, 0);
+        }
+
+        self.vertices.clear();
+    }
+}
+
+/// Normalized section of `texture` covered by the pixel space `r_rect`, the whole texture if None
+pub fn uv_rect(texture: &Texture, r_rect: Option<Rect>) -> Rect {
+    match r_rect {
+        // 256, 256 ,256 ,256 => (0.5, 0.5, 0.5, 0.5) @ 512x512
+        Some(r) => Rect {
+            x: r.x / texture.width as f32,
+            y: r.y / texture.height as f32,
+            w: r.w / texture.width as f32,
+            h: r.h / texture.height as f32,
+        },
+        None => Rect {
+            x: 0.,
+            y: 0.,
+            w: 1.,
+            h: 1.,
+        },
+    }
+}
+
+/// Creates the dynamic vertex buffer and the index buffer shared by every batch
+pub fn create_batch_buffers(vao: u32, vbo: u32, ebo: u32) {
+    let indices: Vec<u32> = (0..MAX_BATCH_QUADS as u32)
+        .flat_map(|q| [0, 1, 3, 1, 2, 3].map(|i| q * 4 + i))
+        .collect();
+    let size_of_vertex = std::mem::size_of::<BatchVertex>();
+
+    unsafe {
+        BindVertexArray(vao);
+        BindBuffer(ARRAY_BUFFER, vbo);
+        BufferData(
+            ARRAY_BUFFER,
+            (size_of_vertex * MAX_BATCH_QUADS * 4).try_into().unwrap(),
+            std::ptr::null::<c_void>(),
+            DYNAMIC_DRAW,
+        );
+
+        let stride = size_of_vertex.try_into().unwrap();
+        let attributes = [(3, 0), (2, 3), (4, 5)];
+        for (location, (size, offset)) in attributes.into_iter().enumerate() {
+            VertexAttribPointer(
+                location as u32,
+                size,
+                FLOAT,
+                FALSE,
+                stride,
+                (offset * std::mem::size_of::<f32>()) as *const c_void,
+            );
+            EnableVertexAttribArray(location as u32);
+        }
+
+        BindBuffer(ELEMENT_ARRAY_BUFFER, ebo);
+        BufferData(
+            ELEMENT_ARRAY_BUFFER,
+            std::mem::size_of_val(indices.as_slice())
+                .try_into()
+                .unwrap(),
+            indices.as_ptr() as *const c_void,
+            STATIC_DRAW,
+        );
+        BindVertexArray(0);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::core::renderer::app_gl::{release_program, uniform_location, Texture};
+use crate::core::renderer::uniform::UniformValue;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use gl::*;
+
+/// Vertex shader used by materials which only replace the fragment shader. Material vertex
+/// shaders get the sprite batch layout, clip space `in_pos`, `in_uv` and `in_color`.
+pub static DEFAULT_MATERIAL_VERTEX_SHADER: &str = "res/glsl/imagev.glsl";
+
+/// Linked program of a vertex and fragment shader pair, see `Resources::load_shader`
+#[derive(Debug)]
+pub struct Shader {
+    pub program: u32,
+    pub vertex_path: String,
+    pub fragment_path: String,
+    locations: RefCell<HashMap<String, i32>>,
+}
+
+impl Shader {
+    pub fn new(program: u32, vertex_path: &str, fragment_path: &str) -> Self {
+        Self {
+            program,
+            vertex_path: vertex_path.to_string(),
+            fragment_path: fragment_path.to_string(),
+            locations: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Location of a uniform, looked up once per name. -1 when the program does not use it.
+    pub fn location(&self, name: &str) -> i32 {
+        *self
+            .locations
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert_with(|| uniform_location(self.program, name))
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        release_program(self.program);
+    }
+}
+
+/// Input of a Material's shader
+#[derive(Debug, Clone, Copy)]
+pub enum MaterialUniform {
+    Value(UniformValue),
+
+    /// Sampled from a texture unit after the one holding the rendered texture
+    Texture(Texture),
+
+    /// Seconds since the renderer started, multiplied by the given speed
+    Time(f32),
+}
+
+static NEXT_MATERIAL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Custom shader and its uniforms for an Image or Text. Quads only batch with quads using the
+/// same Material, so cloning one gives it a new id.
+#[derive(Debug)]
+pub struct Material {
+    id: u64,
+    pub shader: Rc<Shader>,
+    pub uniforms: BTreeMap<String, MaterialUniform>,
+}
+
+impl Material {
+    pub fn new(shader: Rc<Shader>) -> Self {
+        Self {
+            id: NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed),
+            shader,
+            uniforms: BTreeMap::new(),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn set_uniform(&mut self, name: &str, uniform: MaterialUniform) -> &mut Self {
+        self.uniforms.insert(name.to_string(), uniform);
+        self
+    }
+
+    /// Sets the uniforms on the material's program, which must be in use
+    pub fn apply(&self, time: f32) {
+        let mut unit = 1;
+        for (name, uniform) in &self.uniforms {
+            let location = self.shader.location(name);
+            match *uniform {
+                MaterialUniform::Value(value) => value.apply(location),
+                MaterialUniform::Time(speed) => UniformValue::Float(time * speed).apply(location),
+                MaterialUniform::Texture(texture) => unsafe {
+                    ActiveTexture(TEXTURE0 + unit);
+                    BindTexture(TEXTURE_2D, texture.texture_id);
+                    ActiveTexture(TEXTURE0);
+                    Uniform1i(location, unit as i32);
+                    unit += 1;
+                },
+            }
+        }
+    }
+}
+
+impl Clone for Material {
+    fn clone(&self) -> Self {
+        Self {
+            uniforms: self.uniforms.clone(),
+            ..Material::new(self.shader.clone())
+        }
+    }
+}
@@ -1,13 +1,24 @@
 pub mod app_gl;
+pub mod atlas;
+pub mod batch;
+pub mod material;
+pub mod post;
+pub mod uniform;
 pub mod window;
 
 extern crate nalgebra_glm as glm;
 
 use crate::{
     core::renderer::app_gl::AppGL,
+    core::renderer::batch::{BatchKey, BatchQuad, SpriteBatch},
+    core::renderer::material::Material,
+    core::renderer::post::PostProcess,
     util::{rect::Rect, scale::Scale},
 };
 
+use gl::*;
+use std::cell::RefCell;
+
 #[derive(Debug)]
 pub struct Viewport {
     pub offset: [f32; 2],
@@ -23,22 +34,43 @@ impl Viewport {
     }
 }
 
+/// Offscreen framebuffer being rendered into, see `RenderTarget`
+#[derive(Debug, Clone, Copy)]
+pub struct TargetSpace {
+    pub fbo: u32,
+    pub width: f32,
+    pub height: f32,
+}
+
 pub struct Renderer {
     pub offset: (f32, f32),
+
+    /// Effects run over the whole frame, toggled from game code. Declared before `gl` so it is
+    /// dropped while GL is still loaded.
+    pub post: PostProcess,
     pub gl: AppGL,
     pub id: glm::TMat4<f32>,
     pub ortho: glm::TMat4<f32>,
     pub viewport: Viewport,
+
+    /// Images queued during `render_components`, drawn when their batch is flushed
+    pub batch: RefCell<SpriteBatch>,
+
+    /// Offscreen targets being rendered into, the innermost one last
+    targets: RefCell<Vec<TargetSpace>>,
 }
 
 impl Default for Renderer {
     fn default() -> Self {
         Self {
             offset: (0., 0.),
+            post: PostProcess::default(),
             id: glm::identity::<f32, 4>(),
             ortho: glm::ortho(0.0f32, 1920., 0., 1080., -10., 100.),
             viewport: Viewport::new(1920., 1080.),
             gl: AppGL::default(),
+            batch: RefCell::new(SpriteBatch::default()),
+            targets: RefCell::new(Vec::new()),
         }
     }
 }
@@ -60,18 +92,105 @@ impl Renderer {
         self.viewport = Viewport::new(width, height);
     }
 
-    pub fn make_mvp(&self, cfg: &MVPConfig) -> glm::TMat4<f32> {
-        let scale = glm::make_vec3(&[cfg.rect.w * cfg.scale.x, cfg.rect.h * cfg.scale.y, 1.]);
+    /// Queues a quad in the sprite batch. A Material's program replaces `key.program`.
+    pub fn push_quad(&self, key: BatchKey, material: Option<&Material>, quad: &BatchQuad) {
+        let key = match material {
+            Some(material) => BatchKey {
+                program: material.shader.program,
+                material: material.id(),
+                ..key
+            },
+            None => key,
+        };
+        self.batch.borrow_mut().push(&self.gl, key, material, quad);
+    }
+
+    /// Draws the queued quads, required before drawing anything outside of the batch
+    pub fn flush_batch(&self) {
+        self.batch.borrow_mut().flush(&self.gl);
+    }
+
+    /// Advances the clock of time uniforms and redirects the frame offscreen when a post effect
+    /// is enabled
+    pub fn begin_frame(&mut self, dt: f32) {
+        self.batch.get_mut().time += dt;
+        if let Some(target) = self.post.begin(self.viewport.window_size, dt) {
+            self.push_target(target);
+        }
+    }
+
+    /// Flushes the batch once the whole tree has rendered and runs the post effects over the
+    /// frame into the window
+    pub fn end_frame(&mut self) {
+        self.flush_batch();
+        if !self.post.is_active() {
+            return;
+        }
+
+        self.pop_target();
+        self.post.run(&self.gl, self.viewport.window_size);
+    }
+
+    /// Renders into an offscreen framebuffer, cleared to transparent, until `pop_target`. Its
+    /// space is the same as the window's, y-down with the origin at the top left.
+    pub fn push_target(&self, target: TargetSpace) {
+        self.flush_batch();
+        self.targets.borrow_mut().push(target);
+        self.bind_target();
+
+        unsafe {
+            Clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Goes back to rendering into the previous target or the window
+    pub fn pop_target(&self) {
+        self.flush_batch();
+        self.targets.borrow_mut().pop();
+        self.bind_target();
+    }
+
+    fn bind_target(&self) {
+        let (fbo, width, height) = match self.targets.borrow().last() {
+            Some(t) => (t.fbo, t.width, t.height),
+            None => (0, self.viewport.window_size.0, self.viewport.window_size.1),
+        };
+
+        unsafe {
+            BindFramebuffer(FRAMEBUFFER, fbo);
+            Viewport(0, 0, width as i32, height as i32);
+        }
+    }
+
+    /// Builds the MVP for a unit quad described by `cfg` in the local space of `world`.
+    /// `world` maps local space into screen space, which is y-down with the origin at the top left.
+    pub fn make_mvp(&self, world: &glm::TMat4<f32>, cfg: &MVPConfig) -> glm::TMat4<f32> {
+        // Screen space to GL space, y-up with the origin at the bottom left
+        let screen = glm::translate(
+            &self.id,
+            &glm::make_vec3(&[
+                self.offset.0,
+                self.viewport.window_size.1 - self.offset.1,
+                0.,
+            ]),
+        );
+        let flip = glm::scale(&self.id, &glm::make_vec3(&[1., -1., 1.]));
+
+        // The quad is flipped back so texture coordinates stay upright after the screen flip
+        let scale = glm::make_vec3(&[cfg.rect.w * cfg.scale.x, -cfg.rect.h * cfg.scale.y, 1.]);
         let scale_model = glm::scale(&self.id, &scale);
         let rotate_vec = glm::make_vec3(&[0., 0., 1.]);
-        let rotate_model = glm::rotate(&self.id, cfg.rotation, &rotate_vec);
-        let mve = glm::make_vec3(&[
-            cfg.rect.x + self.offset.0,
-            self.viewport.window_size.1 - cfg.rect.y - self.offset.1,
-            0.,
-        ]);
-        let view = glm::translate(&self.id, &mve);
-        let model = rotate_model * scale_model;
-        self.ortho * view * model
+        let rotate_model = glm::rotate(&self.id, -cfg.rotation, &rotate_vec);
+        let mve = glm::make_vec3(&[cfg.rect.x, cfg.rect.y, 0.]);
+        let translate_model = glm::translate(&self.id, &mve);
+        let model = translate_model * rotate_model * scale_model;
+
+        // Targets are stored with their top row first, which is how Images sample textures
+        if let Some(target) = self.targets.borrow().last() {
+            let ortho = glm::ortho(0.0f32, target.width, 0., target.height, -10., 100.);
+            return ortho * world * model;
+        }
+
+        self.ortho * screen * flip * world * model
     }
 }
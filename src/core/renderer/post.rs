@@ -0,0 +1,203 @@
+use crate::core::renderer::app_gl::{
+    create_and_link_program, create_framebuffer, release_framebuffer, release_program,
+    release_texture, uniform_location, AppGL, Texture,
+};
+use crate::core::renderer::uniform::UniformValue;
+use crate::core::renderer::TargetSpace;
+
+use core::ffi::c_void;
+use std::collections::{BTreeMap, HashMap};
+
+use gl::*;
+
+/// Vertex shader shared by every effect, it covers the target with the unit quad
+static POST_VERTEX_SHADER: &str = "res/glsl/post/postv.glsl";
+
+/// Fullscreen fragment shader run over the frame. Every effect gets the frame in `tex1`, its
+/// size in pixels in `resolution` and the seconds since the chain started in `time`.
+#[derive(Debug)]
+pub struct PostEffect {
+    pub name: String,
+    pub enabled: bool,
+    program: u32,
+    uniforms: BTreeMap<String, UniformValue>,
+    locations: HashMap<String, i32>,
+}
+
+impl PostEffect {
+    pub fn set_uniform(&mut self, name: &str, value: UniformValue) -> &mut Self {
+        self.uniforms.insert(name.to_string(), value);
+        self
+    }
+
+    fn location(&mut self, name: &str) -> i32 {
+        let program = self.program;
+        *self
+            .locations
+            .entry(name.to_string())
+            .or_insert_with(|| uniform_location(program, name))
+    }
+
+    fn apply_uniforms(&mut self, resolution: (f32, f32), time: f32, flip_y: bool) {
+        let builtins = [
+            (
+                "resolution",
+                UniformValue::Vec2([resolution.0, resolution.1]),
+            ),
+            ("time", UniformValue::Float(time)),
+            ("flip_y", UniformValue::Float(flip_y as u8 as f32)),
+        ];
+        for (name, value) in builtins {
+            value.apply(self.location(name));
+        }
+
+        let uniforms: Vec<_> = self.uniforms.iter().map(|(n, v)| (n.clone(), *v)).collect();
+        for (name, value) in uniforms {
+            value.apply(self.location(&name));
+        }
+    }
+}
+
+/// Ordered chain of PostEffects. While any effect is enabled the frame is rendered offscreen
+/// and each enabled effect draws the output of the previous one, the last into the window.
+#[derive(Debug, Default)]
+pub struct PostProcess {
+    effects: Vec<PostEffect>,
+
+    /// Framebuffers the chain ping-pongs between, sized to the window
+    targets: Vec<(u32, Texture)>,
+    active: bool,
+    time: f32,
+}
+
+impl PostProcess {
+    /// Appends an effect to the end of the chain, disabled until turned on
+    pub fn add_effect(
+        &mut self,
+        name: &str,
+        fragment_shader: &str,
+    ) -> Result<&mut PostEffect, String> {
+        if self.effect(name).is_some() {
+            return Err(format!("Post effect {} already exists", name));
+        }
+
+        let program = create_and_link_program(POST_VERTEX_SHADER, fragment_shader)?;
+        self.effects.push(PostEffect {
+            name: name.to_string(),
+            enabled: false,
+            program,
+            uniforms: BTreeMap::new(),
+            locations: HashMap::new(),
+        });
+        Ok(self.effects.last_mut().unwrap())
+    }
+
+    pub fn effect(&mut self, name: &str) -> Option<&mut PostEffect> {
+        self.effects.iter_mut().find(|e| e.name == name)
+    }
+
+    /// Turns an effect on or off, returns whether it is now enabled
+    pub fn toggle(&mut self, name: &str) -> bool {
+        match self.effect(name) {
+            Some(effect) => {
+                effect.enabled = !effect.enabled;
+                effect.enabled
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the current frame is being rendered offscreen for the chain
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Target to render the frame into, None when no effect is enabled
+    pub fn begin(&mut self, size: (f32, f32), dt: f32) -> Option<TargetSpace> {
+        self.time += dt;
+        self.active = self.effects.iter().any(|e| e.enabled);
+        if !self.active {
+            return None;
+        }
+
+        let (width, height) = (size.0 as u32, size.1 as u32);
+        let resized = self
+            .targets
+            .first()
+            .is_none_or(|(_, t)| (t.width, t.height) != (width, height));
+        if resized {
+            self.release_targets();
+            for _ in 0..2 {
+                match create_framebuffer(width, height) {
+                    Ok(target) => self.targets.push(target),
+                    Err(err) => {
+                        println!("Failed to create post process target: {}", err);
+                        self.release_targets();
+                        self.active = false;
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(TargetSpace {
+            fbo: self.targets[0].0,
+            width: size.0,
+            height: size.1,
+        })
+    }
+
+    /// Runs the enabled effects over the frame rendered since `begin`
+    pub fn run(&mut self, gl: &AppGL, size: (f32, f32)) {
+        if !self.active {
+            return;
+        }
+
+        let targets = self.targets.clone();
+        let time = self.time;
+        let mut effects: Vec<_> = self.effects.iter_mut().filter(|e| e.enabled).collect();
+        let last = match effects.len() {
+            0 => return,
+            n => n - 1,
+        };
+
+        unsafe {
+            Disable(BLEND);
+            BindVertexArray(gl.vao);
+            BindBuffer(ELEMENT_ARRAY_BUFFER, gl.ebo);
+
+            for (i, effect) in effects.iter_mut().enumerate() {
+                let input = targets[i % 2].1;
+                let output = match i == last {
+                    true => 0,
+                    false => targets[(i + 1) % 2].0,
+                };
+
+                BindFramebuffer(FRAMEBUFFER, output);
+                Viewport(0, 0, size.0 as i32, size.1 as i32);
+                UseProgram(effect.program);
+
+                // Offscreen targets store the top row first, the window the bottom one
+                effect.apply_uniforms(size, time, i == last);
+                BindTexture(TEXTURE_2D, input.texture_id);
+                DrawElements(TRIANGLES, 6, UNSIGNED_INT, std::ptr::null::<c_void>());
+            }
+        }
+    }
+
+    fn release_targets(&mut self) {
+        for (fbo, texture) in self.targets.drain(..) {
+            release_framebuffer(fbo);
+            release_texture(texture.texture_id);
+        }
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        self.release_targets();
+        for effect in &self.effects {
+            release_program(effect.program);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use gl::*;
+use serde::{Deserialize, Serialize};
+
+/// Value of a user set shader uniform. Written as a number, an array of 2 to 4 numbers or a
+/// column major 4x4 matrix of 16 numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UniformValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Mat4([f32; 16]),
+}
+
+impl UniformValue {
+    /// Sets the uniform at `location` of the program in use
+    pub fn apply(&self, location: i32) {
+        if location < 0 {
+            return;
+        }
+
+        unsafe {
+            match *self {
+                UniformValue::Float(v) => Uniform1f(location, v),
+                UniformValue::Vec2([x, y]) => Uniform2f(location, x, y),
+                UniformValue::Vec3([x, y, z]) => Uniform3f(location, x, y, z),
+                UniformValue::Vec4([x, y, z, w]) => Uniform4f(location, x, y, z, w),
+                UniformValue::Mat4(m) => UniformMatrix4fv(location, 1, FALSE, m.as_ptr()),
+            }
+        }
+    }
+}
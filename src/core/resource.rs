@@ -1,4 +1,7 @@
 use crate::core::renderer::app_gl::*;
+use crate::core::renderer::atlas::{AtlasRegion, TextureAtlas};
+use crate::core::renderer::material::{Material, Shader, DEFAULT_MATERIAL_VERTEX_SHADER};
+use crate::util::rect::Rect;
 use sfml::{audio::SoundBuffer, window::Context, SfBox};
 use std::fs::File;
 
@@ -64,6 +67,14 @@ pub struct Resources {
     text_data: HashMap<String, Texture>,
     font_data: HashMap<String, Font>,
 
+    /// Small images and text packed into shared pages, keyed by path and by text
+    atlas: TextureAtlas,
+    atlas_images: HashMap<String, AtlasRegion>,
+    atlas_text: HashMap<String, AtlasRegion>,
+
+    /// Linked programs keyed by their vertex and fragment shader paths
+    shaders: HashMap<(String, String), Rc<Shader>>,
+
     remote_image_loading: HashMap<String, u32>,
     remote_image_work_tx: Sender<ImageLoadPayload>,
     remote_image_rx: Receiver<ImageLoadPayload>,
@@ -148,6 +159,10 @@ impl Default for Resources {
             texture_data: HashMap::new(),
             text_data: HashMap::new(),
             font_data: HashMap::new(),
+            atlas: TextureAtlas::default(),
+            atlas_images: HashMap::new(),
+            atlas_text: HashMap::new(),
+            shaders: HashMap::new(),
             remote_image_loading: HashMap::new(),
             remote_image_work_tx: in_tx,
             remote_image_rx: out_rx,
@@ -197,6 +212,132 @@ impl Resources {
         None
     }
 
+    /// Path or url a loaded texture was created from
+    pub fn texture_path(&self, texture_id: u32) -> Option<&str> {
+        self.texture_data
+            .iter()
+            .find(|(_, texture)| texture.texture_id == texture_id)
+            .map(|(path, _)| path.as_str())
+    }
+
+    /// Path an atlas region was packed from
+    pub fn atlas_path(&self, texture_id: u32, rect: &Rect) -> Option<&str> {
+        self.atlas_images
+            .iter()
+            .find(|(_, region)| {
+                region.texture.texture_id == texture_id
+                    && (region.rect.x, region.rect.y) == (rect.x, rect.y)
+            })
+            .map(|(path, _)| path.as_str())
+    }
+
+    /// RGBA pixels of a texture, read back from the GPU on first use and again after it changes
+    pub fn texture_pixels(&self, texture: &Texture) -> Rc<Vec<u8>> {
+        cached_texture_pixels(texture)
+    }
+
+    /// Packs a small image into a shared atlas page. Images too large for the atlas are loaded
+    /// as their own texture and get a region covering all of it.
+    pub fn load_image_into_atlas(&mut self, image_file_path: &str) -> Result<AtlasRegion, String> {
+        if let Some(region) = self.atlas_images.get(image_file_path) {
+            return Ok(*region);
+        }
+
+        let (width, height, pixels) = load_image_pixels_from_disk(image_file_path)?;
+        let region = match TextureAtlas::fits(width, height) {
+            true => self.atlas.insert(width, height, &pixels)?,
+            false => AtlasRegion::whole(self.load_image_from_disk(image_file_path)?),
+        };
+        self.atlas_images
+            .insert(image_file_path.to_string(), region);
+        Ok(region)
+    }
+
+    /// Renders text into a shared atlas page as white pixels, tint it with an Image's color. Text
+    /// too large for the atlas gets a texture of its own and a region covering all of it.
+    pub fn load_text_into_atlas(
+        &mut self,
+        text_load_info: &TextLoadInfo,
+    ) -> Result<AtlasRegion, String> {
+        let key = text_key(text_load_info);
+        if let Some(region) = self.atlas_text.get(&key) {
+            return Ok(*region);
+        }
+
+        let font_data = self.load_font(&text_load_info.font_path);
+        let (width, height, pixels) = render_text_to_pixels(RenderTextBundle {
+            text: &text_load_info.text,
+            text_size: text_load_info.font_size,
+            font_data: &font_data,
+        });
+
+        let region = match TextureAtlas::fits(width, height) {
+            true => self.atlas.insert(width, height, &pixels)?,
+            false => {
+                let texture = create_texture(width, height)?;
+                let region = AtlasRegion::whole(texture);
+                upload_sub_image(&texture, &region.rect, &pixels);
+                region
+            }
+        };
+        self.atlas_text.insert(key, region);
+        Ok(region)
+    }
+
+    fn load_font(&mut self, font_path: &str) -> Rc<Vec<u8>> {
+        self.font_data
+            .entry(font_path.to_string())
+            .or_insert_with(|| {
+                let mut source = File::open(font_path).unwrap();
+                let mut contents = Vec::new();
+                source
+                    .read_to_end(&mut contents)
+                    .map_err(|err| println!("{:?}", err))
+                    .ok();
+                Font {
+                    data: Rc::new(contents),
+                }
+            })
+            .data
+            .clone()
+    }
+
+    /// Compiles and links a vertex and fragment shader pair, once per pair of paths
+    pub fn load_shader(
+        &mut self,
+        vertex_shader_path: &str,
+        fragment_shader_path: &str,
+    ) -> Result<Rc<Shader>, String> {
+        let key = (
+            vertex_shader_path.to_string(),
+            fragment_shader_path.to_string(),
+        );
+        if let Some(shader) = self.shaders.get(&key) {
+            return Ok(shader.clone());
+        }
+
+        let program = create_and_link_program(vertex_shader_path, fragment_shader_path)?;
+        let shader = Rc::new(Shader::new(
+            program,
+            vertex_shader_path,
+            fragment_shader_path,
+        ));
+        self.shaders.insert(key, shader.clone());
+        Ok(shader)
+    }
+
+    /// New Material without uniforms, the vertex shader defaults to the sprite batch one
+    pub fn load_material(
+        &mut self,
+        vertex_shader_path: Option<&str>,
+        fragment_shader_path: &str,
+    ) -> Result<Material, String> {
+        let vertex_shader_path = vertex_shader_path.unwrap_or(DEFAULT_MATERIAL_VERTEX_SHADER);
+        Ok(Material::new(
+            self.load_shader(vertex_shader_path, fragment_shader_path)?,
+        ))
+    }
+
     pub fn load_audio_data(
         &mut self,
         audio_file_path: &str,
@@ -301,32 +442,39 @@ impl Resources {
         }
     }
 
+    /// Texture of rendered text, kept until Resources is dropped. Text which changes often should
+    /// use `render_text_texture` instead.
     pub fn load_text_texture(&mut self, text_load_info: &TextLoadInfo) -> Result<Texture, String> {
-        let key = text_load_info.text.clone();
+        let key = text_key(text_load_info);
         if let Some(id) = self.text_data.get(&key) {
             Ok(*id)
         } else {
-            let font_path = text_load_info.font_path.clone();
-            self.font_data.entry(font_path.clone()).or_insert_with(|| {
-                let mut source = File::open(font_path.clone()).unwrap();
-                let mut contents = Vec::new();
-                source
-                    .read_to_end(&mut contents)
-                    .map_err(|err| println!("{:?}", err))
-                    .ok();
-                Font {
-                    data: Rc::new(contents),
-                }
-            });
-            let text_result = render_text_to_texture(RenderTextBundle {
-                text: &text_load_info.text,
-                text_size: text_load_info.font_size,
-                font_data: &self.font_data.get(&font_path).unwrap().data.clone(),
-            })?;
+            let text_result = self.render_text_texture(text_load_info)?;
             self.text_data.insert(key, text_result);
             Ok(text_result)
         }
     }
+
+    /// Renders text into a new texture which is not cached, the caller releases it
+    pub fn render_text_texture(
+        &mut self,
+        text_load_info: &TextLoadInfo,
+    ) -> Result<Texture, String> {
+        let font_data = self.load_font(&text_load_info.font_path);
+        Ok(render_text_to_texture(RenderTextBundle {
+            text: &text_load_info.text,
+            text_size: text_load_info.font_size,
+            font_data: &font_data,
+        })?)
+    }
+}
+
+/// Cache key of rendered text, the same string renders differently per font and size
+fn text_key(text_load_info: &TextLoadInfo) -> String {
+    format!(
+        "{}:{}:{}",
+        text_load_info.font_path, text_load_info.font_size, text_load_info.text
+    )
 }
 
 impl Drop for Resources {
@@ -338,5 +486,16 @@ impl Drop for Resources {
         for texture_info in self.text_data.values() {
             release_texture(texture_info.texture_id);
         }
+
+        let pages: Vec<u32> = self.atlas.pages().map(|page| page.texture_id).collect();
+        for page in &pages {
+            release_texture(*page);
+        }
+
+        for region in self.atlas_text.values() {
+            if !pages.contains(&region.texture.texture_id) {
+                release_texture(region.texture.texture_id);
+            }
+        }
     }
 }
@@ -0,0 +1,813 @@
+pub mod prefab;
+
+use crate::app::App;
+use crate::core::component::audio_clip::AudioClip;
+use crate::core::component::draggable::Draggable;
+use crate::core::component::drop_target::DropTarget;
+use crate::core::component::focusable::Focusable;
+use crate::core::component::image::{Image, ImageRenderType};
+use crate::core::component::pickable::Pickable;
+use crate::core::component::render_target::{RedrawMode, RenderTarget};
+use crate::core::component::text::Text;
+use crate::core::component::transform::Transform;
+use crate::core::entity::animated_image::{
+    animated_image_add_animation, animated_image_set_animation, make_animated_image, AnimatedImage,
+    ANIMATED_IMAGE_TEXTURE_NAME,
+};
+use crate::core::entity::behavior::EntityBehavior;
+use crate::core::entity::{Entity, RenderableEntity};
+use crate::core::renderer::batch::BlendMode;
+use crate::core::renderer::material::{Material, MaterialUniform, DEFAULT_MATERIAL_VERTEX_SHADER};
+use crate::core::renderer::uniform::UniformValue;
+use crate::core::resource::{Resources, TextLoadInfo};
+
+use crate::util::alpha::Alpha;
+use crate::util::color::Color;
+use crate::util::rect::Rect;
+use crate::util::scale::Scale;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use self::prefab::load_resolved;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(String),
+    Parse(String),
+    Resource(String),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SceneError::Io(msg) => write!(f, "scene io error: {}", msg),
+            SceneError::Parse(msg) => write!(f, "scene parse error: {}", msg),
+            SceneError::Resource(msg) => write!(f, "scene resource error: {}", msg),
+        }
+    }
+}
+
+/// Serialized form of an Entity and its subtree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneEntity {
+    pub name: String,
+    pub zindex: i32,
+    pub active: bool,
+    pub visible: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub behavior: Option<SceneBehavior>,
+    pub components: Vec<SceneComponent>,
+    pub children: Vec<SceneEntity>,
+}
+
+impl Default for SceneEntity {
+    fn default() -> Self {
+        Self {
+            name: "".to_string(),
+            zindex: 0,
+            active: true,
+            visible: true,
+            tags: Vec::new(),
+            behavior: None,
+            components: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// EntityBehavior to attach to the Entity. `kind` selects a factory registered with the
+/// BehaviorRegistry and the remaining fields are passed to it as parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneBehavior {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+pub type BehaviorFactory = fn(&Map<String, Value>) -> Result<Box<dyn EntityBehavior>, String>;
+
+/// Maps SceneBehavior kinds to the functions which build them
+#[derive(Default)]
+pub struct BehaviorRegistry {
+    factories: HashMap<String, BehaviorFactory>,
+}
+
+impl BehaviorRegistry {
+    pub fn register(&mut self, kind: &str, factory: BehaviorFactory) {
+        self.factories.insert(kind.to_string(), factory);
+    }
+
+    pub fn create(&self, desc: &SceneBehavior) -> Result<Box<dyn EntityBehavior>, SceneError> {
+        match self.factories.get(&desc.kind) {
+            Some(factory) => factory(&desc.params).map_err(SceneError::Parse),
+            None => Err(SceneError::Parse(format!(
+                "No behavior registered for: {}",
+                desc.kind
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum SceneComponent {
+    Image(ImageDesc),
+    Text(TextDesc),
+    AudioClip(AudioClipDesc),
+
+    /// Older scenes may still describe a translation only "Offset"
+    #[serde(alias = "Offset")]
+    Transform(Transform),
+
+    /// Turns the Entity into an animated image, see `make_animated_image`
+    AnimatedImage(AnimatedImageDesc),
+
+    /// Makes the Entity a pointer target, see `core::pointer`
+    Pickable(Pickable),
+
+    /// Lets the Entity take keyboard focus, see `core::focus`
+    Focusable(Focusable),
+
+    /// Lets the pointer drag the Entity, see `core::drag`
+    Draggable(Draggable),
+
+    /// Lets Draggable Entities be dropped on the Entity
+    DropTarget(DropTarget),
+
+    /// Renders the Entity's subtree offscreen, see `RenderTarget`
+    RenderTarget(RenderTargetDesc),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageDesc {
+    pub name: String,
+
+    /// Disk path of the texture. Images without a path are created empty.
+    pub path: Option<String>,
+
+    /// Packs the texture into a shared atlas page, see `Resources::load_image_into_atlas`
+    pub atlas: bool,
+    pub x: f32,
+    pub y: f32,
+
+    /// Defaults to the size of the texture
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub rotation: f32,
+    pub scale: Scale,
+    pub zindex: i32,
+    pub color: Color,
+    pub alpha: f32,
+    pub r_rect: Option<Rect>,
+    pub render_type: Option<ImageRenderType>,
+    pub blend: BlendMode,
+    pub material: Option<MaterialDesc>,
+}
+
+impl Default for ImageDesc {
+    fn default() -> Self {
+        Self {
+            name: "".to_string(),
+            path: None,
+            atlas: false,
+            x: 0.,
+            y: 0.,
+            width: None,
+            height: None,
+            rotation: 0.,
+            scale: Scale::default(),
+            zindex: 0,
+            color: Color::default(),
+            alpha: 1.,
+            r_rect: None,
+            render_type: None,
+            blend: BlendMode::Alpha,
+            material: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextDesc {
+    pub name: String,
+    pub text: String,
+    pub font_path: String,
+    pub font_size: isize,
+    pub x: i32,
+    pub y: i32,
+    pub rotation: f32,
+    pub zindex: i32,
+    pub color: Color,
+    pub alpha: f32,
+    pub material: Option<MaterialDesc>,
+}
+
+impl Default for TextDesc {
+    fn default() -> Self {
+        let text_load_info = TextLoadInfo::default();
+        Self {
+            name: "".to_string(),
+            text: text_load_info.text,
+            font_path: text_load_info.font_path,
+            font_size: text_load_info.font_size,
+            x: 0,
+            y: 0,
+            rotation: 0.,
+            zindex: 0,
+            color: Color::default(),
+            alpha: 1.,
+            material: None,
+        }
+    }
+}
+
+/// Shader an Image or Text is drawn with, see `Resources::load_material`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialDesc {
+    /// Defaults to the sprite batch vertex shader
+    #[serde(default)]
+    pub vertex: Option<String>,
+    pub fragment: String,
+    #[serde(default)]
+    pub uniforms: BTreeMap<String, UniformDesc>,
+}
+
+/// Written as a number or an array for values, `{"texture": path}` or `{"time": speed}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UniformDesc {
+    Value(UniformValue),
+    Texture { texture: String },
+    Time { time: f32 },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioClipDesc {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderTargetDesc {
+    pub width: u32,
+    pub height: u32,
+    pub redraw: RedrawMode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimatedImageDesc {
+    pub path: String,
+
+    /// Width/Height of a single cell of the sprite sheet
+    pub width: f32,
+    pub height: f32,
+    pub fps: Option<f32>,
+    pub render_type: Option<ImageRenderType>,
+    pub animations: BTreeMap<String, (usize, usize)>,
+    pub animation: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub scale: Scale,
+}
+
+impl Default for AnimatedImageDesc {
+    fn default() -> Self {
+        Self {
+            path: "".to_string(),
+            width: 0.,
+            height: 0.,
+            fps: None,
+            render_type: None,
+            animations: BTreeMap::new(),
+            animation: None,
+            x: 0.,
+            y: 0.,
+            rotation: 0.,
+            scale: Scale::default(),
+        }
+    }
+}
+
+/// Loads a scene file, resolving any prefab references in it
+pub fn load_scene(app: &mut App, path: &str) -> Result<Entity, SceneError> {
+    let desc: SceneEntity = serde_json::from_value(load_resolved(path)?)
+        .map_err(|e| SceneError::Parse(e.to_string()))?;
+
+    build_entity(app, &desc)
+}
+
+pub fn save_scene(resources: &Resources, root: &mut Entity, path: &str) -> Result<(), SceneError> {
+    let desc = describe_entity(resources, root);
+    let contents =
+        serde_json::to_string_pretty(&desc).map_err(|e| SceneError::Parse(e.to_string()))?;
+
+    std::fs::write(path, contents).map_err(|e| SceneError::Io(e.to_string()))
+}
+
+/// Builds an Entity tree from its description, loading any assets through `app.resource`
+pub fn build_entity(app: &mut App, desc: &SceneEntity) -> Result<Entity, SceneError> {
+    let animated_image = desc.components.iter().find_map(|c| match c {
+        SceneComponent::AnimatedImage(a) => Some(a),
+        _ => None,
+    });
+
+    let mut e = match animated_image {
+        Some(a) => build_animated_image(app, &desc.name, a)?,
+        None => Entity::new(&desc.name),
+    };
+    e.zindex = desc.zindex;
+    e.active = desc.active;
+    e.visible = desc.visible;
+    for tag in &desc.tags {
+        e.add_tag(tag);
+    }
+
+    if let Some(behavior) = &desc.behavior {
+        e.set_boxed_behavior(app.behaviors.create(behavior)?);
+    }
+
+    for c in &desc.components {
+        match c {
+            SceneComponent::Image(i) => e.add_component(build_image(app, i)?),
+            SceneComponent::Text(t) => e.add_component(build_text(app, t)?),
+            SceneComponent::AudioClip(a) => {
+                let buffer = app
+                    .resource
+                    .load_audio_data(&a.path)
+                    .map_err(SceneError::Resource)?;
+                e.add_component(AudioClip::new(&a.name, &a.path, buffer));
+            }
+            SceneComponent::Transform(t) => e.add_component(*t),
+            SceneComponent::Pickable(p) => e.add_component(*p),
+            SceneComponent::Focusable(f) => e.add_component(*f),
+            SceneComponent::Draggable(d) => e.add_component(d.clone()),
+            SceneComponent::DropTarget(d) => e.add_component(d.clone()),
+            SceneComponent::RenderTarget(r) => e.add_component(
+                RenderTarget::new(r.width, r.height, r.redraw).map_err(SceneError::Resource)?,
+            ),
+            SceneComponent::AnimatedImage(_) => {}
+        }
+    }
+
+    for child in &desc.children {
+        e.add_child(build_entity(app, child)?);
+    }
+
+    Ok(e)
+}
+
+fn build_image(app: &mut App, desc: &ImageDesc) -> Result<Image, SceneError> {
+    let mut img = Image::new(&desc.name);
+    match &desc.path {
+        Some(path) if desc.atlas => {
+            let region = app
+                .resource
+                .load_image_into_atlas(path)
+                .map_err(SceneError::Resource)?;
+            img = Image::with_region(&desc.name, &region);
+        }
+        Some(path) => {
+            let texture = app
+                .resource
+                .load_image_from_disk(path)
+                .map_err(SceneError::Resource)?;
+            img = Image::with_texture(
+                &desc.name,
+                &texture,
+                texture.width as f32,
+                texture.height as f32,
+            );
+        }
+        None => {}
+    }
+
+    img.x = desc.x;
+    img.y = desc.y;
+    img.width = desc.width.unwrap_or(img.width);
+    img.height = desc.height.unwrap_or(img.height);
+    img.rotation = desc.rotation;
+    img.scale = desc.scale;
+    img.zindex = desc.zindex;
+    img.color = desc.color;
+    img.alpha = Alpha::new(desc.alpha);
+    img.r_rect = desc.r_rect;
+    img.render_type = desc.render_type;
+    img.blend = desc.blend;
+    img.material = build_material(app, desc.material.as_ref())?;
+
+    Ok(img)
+}
+
+fn build_text(app: &mut App, desc: &TextDesc) -> Result<Text, SceneError> {
+    let mut text = Text::new(&desc.name);
+    if !desc.text.is_empty() {
+        text.update_text(
+            app,
+            &TextLoadInfo {
+                text: desc.text.clone(),
+                font_path: desc.font_path.clone(),
+                font_size: desc.font_size,
+            },
+        );
+    }
+
+    text.x = desc.x;
+    text.y = desc.y;
+    text.rotation = desc.rotation;
+    text.zindex = desc.zindex;
+    text.color = desc.color;
+    text.alpha = Alpha::new(desc.alpha);
+    text.material = build_material(app, desc.material.as_ref())?;
+
+    Ok(text)
+}
+
+fn build_material(
+    app: &mut App,
+    desc: Option<&MaterialDesc>,
+) -> Result<Option<Material>, SceneError> {
+    let desc = match desc {
+        Some(desc) => desc,
+        None => return Ok(None),
+    };
+
+    let mut material = app
+        .resource
+        .load_material(desc.vertex.as_deref(), &desc.fragment)
+        .map_err(SceneError::Resource)?;
+    for (name, uniform) in &desc.uniforms {
+        let uniform = match uniform {
+            UniformDesc::Value(value) => MaterialUniform::Value(*value),
+            UniformDesc::Texture { texture } => MaterialUniform::Texture(
+                app.resource
+                    .load_image_from_disk(texture)
+                    .map_err(SceneError::Resource)?,
+            ),
+            UniformDesc::Time { time } => MaterialUniform::Time(*time),
+        };
+        material.set_uniform(name, uniform);
+    }
+
+    Ok(Some(material))
+}
+
+fn describe_material(resources: &Resources, material: &Material) -> MaterialDesc {
+    let shader = &material.shader;
+    MaterialDesc {
+        vertex: Some(shader.vertex_path.clone())
+            .filter(|path| path != DEFAULT_MATERIAL_VERTEX_SHADER),
+        fragment: shader.fragment_path.clone(),
+        // Textures which were not loaded from a path can not be saved
+        uniforms: material
+            .uniforms
+            .iter()
+            .filter_map(|(name, uniform)| {
+                let uniform = match *uniform {
+                    MaterialUniform::Value(value) => UniformDesc::Value(value),
+                    MaterialUniform::Texture(texture) => UniformDesc::Texture {
+                        texture: resources.texture_path(texture.texture_id)?.to_string(),
+                    },
+                    MaterialUniform::Time(time) => UniformDesc::Time { time },
+                };
+                Some((name.clone(), uniform))
+            })
+            .collect(),
+    }
+}
+
+fn build_animated_image(
+    app: &mut App,
+    name: &str,
+    desc: &AnimatedImageDesc,
+) -> Result<Entity, SceneError> {
+    let mut e = make_animated_image(
+        app,
+        name,
+        &desc.path,
+        desc.width,
+        desc.height,
+        desc.fps,
+        desc.render_type,
+    );
+
+    for (animation, frame_range) in &desc.animations {
+        animated_image_add_animation(&mut e, animation, *frame_range);
+    }
+
+    if let Some(animation) = &desc.animation {
+        animated_image_set_animation(&mut e, animation);
+    }
+
+    e.set_x(desc.x);
+    e.set_y(desc.y);
+    e.set_width(desc.width);
+    e.set_height(desc.height);
+    e.set_rotation(desc.rotation);
+    e.set_scale_x(desc.scale.x);
+    e.set_scale_y(desc.scale.y);
+
+    Ok(e)
+}
+
+/// Describes a live Entity tree. Behaviors are only serialized when they implement `describe`.
+pub fn describe_entity(resources: &Resources, e: &mut Entity) -> SceneEntity {
+    let mut desc = SceneEntity {
+        name: e.name.clone(),
+        zindex: e.zindex,
+        active: e.active,
+        visible: e.visible,
+        tags: e.tags().cloned().collect(),
+        behavior: e.describe_behavior(),
+        ..Default::default()
+    };
+
+    let is_animated_image = e.behavior_mut::<AnimatedImage>().is_some();
+    if is_animated_image {
+        let mut animated_image_desc = AnimatedImageDesc::default();
+        if let Ok(img) = e.find_component::<Image>(ANIMATED_IMAGE_TEXTURE_NAME) {
+            if let Some(path) = img
+                .texture
+                .and_then(|t| resources.texture_path(t.texture_id))
+            {
+                animated_image_desc.path = path.to_string();
+            }
+            animated_image_desc.render_type = img.render_type;
+            animated_image_desc.x = img.x;
+            animated_image_desc.y = img.y;
+            animated_image_desc.rotation = img.rotation;
+            animated_image_desc.scale = img.scale;
+        }
+
+        let animated_image = e.behavior_mut::<AnimatedImage>().unwrap();
+        let cell_size = animated_image.cell_size();
+        animated_image_desc.width = cell_size.0;
+        animated_image_desc.height = cell_size.1;
+        animated_image_desc.fps = Some(animated_image.fps());
+        // Sorted so saved scenes and tree checksums are stable
+        animated_image_desc.animations = animated_image
+            .animations()
+            .iter()
+            .map(|(name, range)| (name.clone(), *range))
+            .collect();
+        animated_image_desc.animation = animated_image.animation().map(|a| a.to_string());
+
+        desc.components
+            .push(SceneComponent::AnimatedImage(animated_image_desc));
+    }
+
+    for cmp in e.components_mut() {
+        let any = cmp.as_any();
+        if let Some(img) = any.downcast_ref::<Image>() {
+            if is_animated_image && img.name == ANIMATED_IMAGE_TEXTURE_NAME {
+                continue;
+            }
+
+            desc.components.push(SceneComponent::Image(ImageDesc {
+                name: img.name.clone(),
+                path: match (img.texture, img.region) {
+                    (Some(t), Some(region)) => resources.atlas_path(t.texture_id, &region),
+                    (Some(t), None) => resources.texture_path(t.texture_id),
+                    (None, _) => None,
+                }
+                .map(|p| p.to_string()),
+                atlas: img.region.is_some(),
+                x: img.x,
+                y: img.y,
+                width: Some(img.width),
+                height: Some(img.height),
+                rotation: img.rotation,
+                scale: img.scale,
+                zindex: img.zindex,
+                color: img.color,
+                alpha: img.alpha.val,
+                r_rect: img.r_rect,
+                render_type: img.render_type,
+                blend: img.blend,
+                material: img
+                    .material
+                    .as_ref()
+                    .map(|m| describe_material(resources, m)),
+            }));
+        } else if let Some(text) = any.downcast_ref::<Text>() {
+            desc.components.push(SceneComponent::Text(TextDesc {
+                name: text.name.clone(),
+                text: text.text.clone(),
+                font_path: text.font_path.clone(),
+                font_size: text.font_size,
+                x: text.x,
+                y: text.y,
+                rotation: text.rotation,
+                zindex: text.zindex,
+                color: text.color,
+                alpha: text.alpha.val,
+                material: text
+                    .material
+                    .as_ref()
+                    .map(|m| describe_material(resources, m)),
+            }));
+        } else if let Some(clip) = any.downcast_ref::<AudioClip>() {
+            desc.components
+                .push(SceneComponent::AudioClip(AudioClipDesc {
+                    name: clip.name.clone(),
+                    path: clip.path.clone(),
+                }));
+        } else if let Some(transform) = any.downcast_ref::<Transform>() {
+            desc.components.push(SceneComponent::Transform(*transform));
+        } else if let Some(pickable) = any.downcast_ref::<Pickable>() {
+            desc.components.push(SceneComponent::Pickable(*pickable));
+        } else if let Some(focusable) = any.downcast_ref::<Focusable>() {
+            desc.components.push(SceneComponent::Focusable(*focusable));
+        } else if let Some(draggable) = any.downcast_ref::<Draggable>() {
+            desc.components
+                .push(SceneComponent::Draggable(draggable.clone()));
+        } else if let Some(drop_target) = any.downcast_ref::<DropTarget>() {
+            desc.components
+                .push(SceneComponent::DropTarget(drop_target.clone()));
+        } else if let Some(target) = any.downcast_ref::<RenderTarget>() {
+            let texture = target.texture();
+            desc.components
+                .push(SceneComponent::RenderTarget(RenderTargetDesc {
+                    width: texture.width,
+                    height: texture.height,
+                    redraw: target.redraw,
+                }));
+        }
+    }
+
+    for child in e.children_mut() {
+        desc.children.push(describe_entity(resources, child));
+    }
+
+    desc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::any::Any;
+    use serde_json::json;
+
+    /// Behavior saved with scenes, built by the `counter` factory
+    struct Counter {
+        count: i64,
+    }
+
+    impl EntityBehavior for Counter {
+        fn describe(&self) -> Option<SceneBehavior> {
+            let mut params = Map::new();
+            params.insert("count".to_string(), json!(self.count));
+            Some(SceneBehavior {
+                kind: "Counter".to_string(),
+                params,
+            })
+        }
+
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn counter(params: &Map<String, Value>) -> Result<Box<dyn EntityBehavior>, String> {
+        match params.get("count").and_then(|c| c.as_i64()) {
+            Some(count) => Ok(Box::new(Counter { count })),
+            None => Err("Counter needs a count".to_string()),
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::default();
+        app.behaviors.register("Counter", counter);
+        app
+    }
+
+    /// Scene using every component which can be built without GL
+    fn scene() -> Value {
+        json!({
+            "name": "root",
+            "zindex": 2,
+            "tags": ["menu"],
+            "behavior": {"type": "Counter", "count": 3},
+            "components": [
+                {
+                    "type": "Transform",
+                    "x": 10.0,
+                    "y": -4.0,
+                    "rotation": 0.5,
+                    "scale": {"x": 2.0, "y": 1.0},
+                    "pivot": [8.0, 8.0],
+                },
+                {"type": "Pickable", "alpha_threshold": 0.5},
+            ],
+            "children": [
+                {
+                    "name": "slot",
+                    "active": false,
+                    "components": [{"type": "DropTarget", "accepts": ["card"]}],
+                },
+                {
+                    "name": "card",
+                    "visible": false,
+                    "components": [
+                        {"type": "Focusable", "tab_index": 1},
+                        {
+                            "type": "Draggable",
+                            "threshold": 2.0,
+                            "axis": "X",
+                            "bounds": {"x": 0.0, "y": 0.0, "w": 100.0, "h": 50.0},
+                            "payload": "card",
+                        },
+                    ],
+                },
+            ],
+        })
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("omega-{}-{}.json", name, std::process::id()));
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn described_entities_match_their_scene() {
+        let mut app = app();
+        let desc: SceneEntity = serde_json::from_value(scene()).unwrap();
+        let mut root = build_entity(&mut app, &desc).unwrap();
+
+        let described = describe_entity(&app.resource, &mut root);
+        assert_eq!(
+            serde_json::to_value(&described).unwrap(),
+            serde_json::to_value(&desc).unwrap()
+        );
+        assert_eq!(described.children[1].name, "card");
+        assert!(!described.children[1].visible);
+    }
+
+    #[test]
+    fn saved_scenes_load_back() {
+        let mut app = app();
+        let desc: SceneEntity = serde_json::from_value(scene()).unwrap();
+        let mut root = build_entity(&mut app, &desc).unwrap();
+        let path = temp_path("scene");
+
+        save_scene(&app.resource, &mut root, &path).unwrap();
+        let loaded = load_scene(&mut app, &path);
+        std::fs::remove_file(&path).unwrap();
+
+        let described = describe_entity(&app.resource, &mut loaded.unwrap());
+        assert_eq!(
+            serde_json::to_value(&described).unwrap(),
+            serde_json::to_value(&desc).unwrap()
+        );
+    }
+
+    #[test]
+    fn bad_files_are_scene_errors() {
+        let mut app = app();
+        let path = temp_path("bad-scene");
+        let load = |app: &mut App, contents: &str| {
+            std::fs::write(&path, contents).unwrap();
+            load_scene(app, &path).map(|_| ())
+        };
+
+        assert!(matches!(
+            load(&mut app, "{\"name\": "),
+            Err(SceneError::Parse(_))
+        ));
+        assert!(matches!(
+            load(&mut app, r#"{"components": [{"type": "Unknown"}]}"#),
+            Err(SceneError::Parse(_))
+        ));
+        assert!(matches!(
+            load(&mut app, r#"{"behavior": {"type": "Counter"}}"#),
+            Err(SceneError::Parse(_))
+        ));
+        assert!(matches!(
+            load(&mut app, r#"{"behavior": {"type": "Missing"}}"#),
+            Err(SceneError::Parse(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            load_scene(&mut app, &path),
+            Err(SceneError::Io(_))
+        ));
+        let mut root = Entity::new("root");
+        let unwritable = format!("{}/scene.json", temp_path("missing-dir"));
+        assert!(matches!(
+            save_scene(&app.resource, &mut root, &unwritable),
+            Err(SceneError::Io(_))
+        ));
+    }
+}
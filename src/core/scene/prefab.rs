@@ -0,0 +1,280 @@
+use crate::app::App;
+use crate::core::entity::Entity;
+use crate::core::scene::{build_entity, SceneEntity, SceneError};
+
+use serde_json::{Map, Value};
+
+/// Key used by scene descriptions to reference a prefab file. Every other field on the same
+/// object overrides the prefab.
+pub static PREFAB_KEY: &str = "prefab";
+
+/// Builds an Entity from the prefab at `path`. `overrides` is a partial scene entity which is
+/// merged over the prefab, eg: `{"name": "ok_button", "components": [{"type": "Transform", "x": 10}]}`.
+pub fn instantiate_prefab(
+    app: &mut App,
+    path: &str,
+    overrides: Value,
+) -> Result<Entity, SceneError> {
+    let mut instance = match overrides {
+        Value::Object(o) => o,
+        Value::Null => Map::new(),
+        _ => {
+            return Err(SceneError::Parse(format!(
+                "Prefab overrides for {} must be an object",
+                path
+            )))
+        }
+    };
+    instance.insert(PREFAB_KEY.to_string(), Value::String(path.to_string()));
+
+    let resolved = resolve_prefabs(Value::Object(instance), &mut Vec::new())?;
+    let desc: SceneEntity =
+        serde_json::from_value(resolved).map_err(|e| SceneError::Parse(e.to_string()))?;
+
+    build_entity(app, &desc)
+}
+
+/// Reads a scene or prefab file with every prefab reference in it resolved
+pub fn load_resolved(path: &str) -> Result<Value, SceneError> {
+    load_resolved_inner(path, &mut Vec::new())
+}
+
+fn load_resolved_inner(path: &str, stack: &mut Vec<String>) -> Result<Value, SceneError> {
+    if stack.iter().any(|p| p == path) {
+        return Err(SceneError::Parse(format!(
+            "Prefab cycle detected: {} -> {}",
+            stack.join(" -> "),
+            path
+        )));
+    }
+
+    let contents = std::fs::read_to_string(path).map_err(|e| SceneError::Io(e.to_string()))?;
+    let value: Value =
+        serde_json::from_str(&contents).map_err(|e| SceneError::Parse(e.to_string()))?;
+
+    stack.push(path.to_string());
+    let resolved = resolve_prefabs(value, stack);
+    stack.pop();
+
+    resolved
+}
+
+/// Replaces prefab references in an entity description, and its children, with the merged result
+fn resolve_prefabs(mut value: Value, stack: &mut Vec<String>) -> Result<Value, SceneError> {
+    if let Some(children) = value.get_mut("children").and_then(|c| c.as_array_mut()) {
+        let resolved = children
+            .drain(..)
+            .map(|c| resolve_prefabs(c, stack))
+            .collect::<Result<Vec<_>, _>>()?;
+        *children = resolved;
+    }
+
+    let prefab_path = value
+        .as_object_mut()
+        .and_then(|o| o.remove(PREFAB_KEY))
+        .map(|p| p.as_str().map(|p| p.to_string()));
+
+    match prefab_path {
+        Some(Some(path)) => {
+            let base = load_resolved_inner(&path, stack)?;
+            Ok(merge_entity(base, value))
+        }
+        Some(None) => Err(SceneError::Parse(
+            "Prefab reference must be a path".to_string(),
+        )),
+        None => Ok(value),
+    }
+}
+
+/// Merges an entity override over its base. Components are matched by type and name, children by
+/// name, and anything without a match in the base is appended.
+fn merge_entity(base: Value, over: Value) -> Value {
+    match (base, over) {
+        (Value::Object(mut b), Value::Object(o)) => {
+            for (k, v) in o {
+                let merged = match (k.as_str(), b.remove(&k)) {
+                    ("components", Some(bv)) => merge_keyed(bv, v, component_key, merge_values),
+                    ("children", Some(bv)) => merge_keyed(bv, v, child_key, merge_entity),
+                    (_, Some(bv)) => merge_values(bv, v),
+                    (_, None) => v,
+                };
+                b.insert(k, merged);
+            }
+
+            Value::Object(b)
+        }
+        (_, o) => o,
+    }
+}
+
+/// Objects are merged field by field, anything else is replaced by the override
+fn merge_values(base: Value, over: Value) -> Value {
+    match (base, over) {
+        (Value::Object(mut b), Value::Object(o)) => {
+            for (k, v) in o {
+                let merged = match b.remove(&k) {
+                    Some(bv) => merge_values(bv, v),
+                    None => v,
+                };
+                b.insert(k, merged);
+            }
+
+            Value::Object(b)
+        }
+        (_, o) => o,
+    }
+}
+
+fn merge_keyed(
+    base: Value,
+    over: Value,
+    key: fn(&Value) -> Option<String>,
+    merge: fn(Value, Value) -> Value,
+) -> Value {
+    match (base, over) {
+        (Value::Array(mut b), Value::Array(o)) => {
+            for v in o {
+                let position =
+                    key(&v).and_then(|k| b.iter().position(|bv| key(bv) == Some(k.clone())));
+                match position {
+                    Some(position) => {
+                        let bv = b.remove(position);
+                        b.insert(position, merge(bv, v));
+                    }
+                    None => b.push(v),
+                }
+            }
+
+            Value::Array(b)
+        }
+        (_, o) => o,
+    }
+}
+
+fn component_key(v: &Value) -> Option<String> {
+    // Offset is an alias of Transform
+    let kind = match v.get("type")?.as_str()? {
+        "Offset" => "Transform",
+        kind => kind,
+    };
+    let name = v.get("name").and_then(|n| n.as_str()).unwrap_or("");
+    Some(format!("{}:{}", kind, name))
+}
+
+fn child_key(v: &Value) -> Option<String> {
+    v.get("name")?.as_str().map(|n| n.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn button() -> Value {
+        json!({
+            "name": "button",
+            "components": [
+                {"type": "Transform", "x": 0, "y": 5, "width": 100},
+                {"type": "Image", "name": "background", "path": "button.png"},
+                {"type": "Image", "name": "icon", "path": "icon.png", "color": [1, 1, 1]},
+            ],
+            "children": [
+                {
+                    "name": "label",
+                    "components": [{"type": "Text", "text": "Ok", "size": 12}],
+                    "children": [
+                        {"name": "shadow", "components": [{"type": "Text", "text": "Ok"}]},
+                    ],
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn overrides_component_fields_in_place() {
+        let merged = merge_entity(
+            button(),
+            json!({
+                "components": [
+                    {"type": "Transform", "x": 10},
+                    {"type": "Image", "name": "icon", "color": [1, 0, 0]},
+                ],
+            }),
+        );
+
+        assert_eq!(
+            merged["components"],
+            json!([
+                {"type": "Transform", "x": 10, "y": 5, "width": 100},
+                {"type": "Image", "name": "background", "path": "button.png"},
+                {"type": "Image", "name": "icon", "path": "icon.png", "color": [1, 0, 0]},
+            ])
+        );
+        assert_eq!(merged["children"], button()["children"]);
+    }
+
+    #[test]
+    fn appends_unmatched_components_and_children() {
+        let merged = merge_entity(
+            button(),
+            json!({
+                "name": "ok_button",
+                "components": [{"type": "Image", "name": "badge", "path": "badge.png"}],
+                "children": [{"name": "tooltip"}],
+            }),
+        );
+
+        assert_eq!(merged["name"], "ok_button");
+        let components = merged["components"].as_array().unwrap();
+        assert_eq!(components.len(), 4);
+        assert_eq!(components[3]["name"], "badge");
+        assert_eq!(
+            merged["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| &c["name"])
+                .collect::<Vec<_>>(),
+            ["label", "tooltip"]
+        );
+    }
+
+    #[test]
+    fn overrides_nested_children_by_name() {
+        let merged = merge_entity(
+            button(),
+            json!({
+                "children": [{
+                    "name": "label",
+                    "children": [
+                        {"name": "shadow", "components": [{"type": "Text", "text": "Cancel"}]},
+                    ],
+                }],
+            }),
+        );
+
+        let label = &merged["children"][0];
+        assert_eq!(
+            label["components"],
+            json!([{"type": "Text", "text": "Ok", "size": 12}])
+        );
+        assert_eq!(
+            label["children"],
+            json!([{"name": "shadow", "components": [{"type": "Text", "text": "Cancel"}]}])
+        );
+    }
+
+    #[test]
+    fn non_objects_replace_the_base() {
+        assert_eq!(
+            merge_values(json!({"a": [1, 2]}), json!({"a": [3]})),
+            json!({"a": [3]})
+        );
+        assert_eq!(merge_values(json!({"a": 1}), json!(null)), json!(null));
+        assert_eq!(
+            merge_keyed(json!([1]), json!({}), child_key, merge_entity),
+            json!({})
+        );
+    }
+}
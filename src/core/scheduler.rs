@@ -0,0 +1,231 @@
+use crate::core::entity::EntityId;
+
+use std::cell::{Cell, RefCell};
+
+/// Shortest allowed repeat interval, a repeating timer with no interval would always be due
+static MIN_INTERVAL: f32 = 0.001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerId(u64);
+
+/// Delivered to the owning Entity as `Event::Timer` each time a timer fires
+#[derive(Debug, Clone)]
+pub struct TimerPayload {
+    pub id: TimerId,
+    pub entity: EntityId,
+    pub name: String,
+
+    /// Intervals which elapsed since the timer last fired. More than 1 when a long frame made a
+    /// repeating timer fall behind, it fires once per frame and reports the intervals it missed.
+    pub count: u32,
+}
+
+#[derive(Debug)]
+struct ScheduledTimer {
+    id: TimerId,
+    entity: EntityId,
+    name: String,
+    remaining: f32,
+
+    /// Some for repeating timers
+    interval: Option<f32>,
+}
+
+/// One-shot and repeating timers tied to Entities. Scheduling only needs `&self` so timers can be
+/// started from `update` as well as event handlers.
+#[derive(Debug)]
+pub struct Scheduler {
+    timers: RefCell<Vec<ScheduledTimer>>,
+    next_id: Cell<u64>,
+
+    /// Multiplier applied to the frame deltatime before timers advance
+    pub time_scale: f32,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            timers: RefCell::new(Vec::new()),
+            next_id: Cell::new(1),
+            time_scale: 1.,
+        }
+    }
+}
+
+impl Scheduler {
+    fn schedule(&self, entity: EntityId, name: &str, delay: f32, interval: Option<f32>) -> TimerId {
+        let id = TimerId(self.next_id.get());
+        self.next_id.set(id.0 + 1);
+
+        self.timers.borrow_mut().push(ScheduledTimer {
+            id,
+            entity,
+            name: name.to_string(),
+            remaining: delay,
+            interval,
+        });
+
+        id
+    }
+
+    /// Fires once after `seconds`
+    pub fn after(&self, entity: EntityId, name: &str, seconds: f32) -> TimerId {
+        self.schedule(entity, name, seconds, None)
+    }
+
+    /// Fires every `seconds` until cancelled
+    pub fn every(&self, entity: EntityId, name: &str, seconds: f32) -> TimerId {
+        let interval = seconds.max(MIN_INTERVAL);
+        self.schedule(entity, name, interval, Some(interval))
+    }
+
+    /// Returns false if the timer already fired or was cancelled
+    pub fn cancel(&self, id: TimerId) -> bool {
+        let mut timers = self.timers.borrow_mut();
+        let before = timers.len();
+        timers.retain(|t| t.id != id);
+        before != timers.len()
+    }
+
+    /// Advances every timer by the scaled deltatime and returns the ones which fired, in firing
+    /// order. Each timer fires at most once, see `TimerPayload::count`. Timers whose Entity is no
+    /// longer `alive` are cancelled.
+    pub fn advance(&self, dt: f32, alive: impl Fn(EntityId) -> bool) -> Vec<TimerPayload> {
+        let dt = dt * self.time_scale;
+        let mut timers = self.timers.borrow_mut();
+        timers.retain(|t| alive(t.entity));
+
+        let mut fired = Vec::new();
+        for t in timers.iter_mut() {
+            t.remaining -= dt;
+            if t.remaining > 0. {
+                continue;
+            }
+
+            let overdue = t.remaining;
+            let mut count = 1;
+            if let Some(interval) = t.interval {
+                count += (-overdue / interval) as u32;
+                t.remaining += interval * count as f32;
+
+                // Rounding can leave the timer due again
+                if t.remaining <= 0. {
+                    count += 1;
+                    t.remaining += interval;
+                }
+            }
+
+            fired.push((
+                overdue,
+                TimerPayload {
+                    id: t.id,
+                    entity: t.entity,
+                    name: t.name.clone(),
+                    count,
+                },
+            ));
+        }
+        timers.retain(|t| t.remaining > 0.);
+
+        // The most overdue timer fired first
+        fired.sort_by(|a, b| a.0.total_cmp(&b.0));
+        fired.into_iter().map(|(_, payload)| payload).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entity::Entity;
+
+    fn fired(scheduler: &Scheduler, dt: f32) -> Vec<(String, u32)> {
+        scheduler
+            .advance(dt, |_| true)
+            .into_iter()
+            .map(|t| (t.name, t.count))
+            .collect()
+    }
+
+    fn timer(name: &str, count: u32) -> (String, u32) {
+        (name.to_string(), count)
+    }
+
+    #[test]
+    fn one_shot_fires_once_when_due() {
+        let scheduler = Scheduler::default();
+        let entity = Entity::new("e").id();
+        scheduler.after(entity, "once", 1.);
+
+        assert!(fired(&scheduler, 0.6).is_empty());
+        assert_eq!(fired(&scheduler, 0.6), [timer("once", 1)]);
+        assert!(fired(&scheduler, 10.).is_empty());
+    }
+
+    #[test]
+    fn repeating_timer_keeps_its_phase() {
+        let scheduler = Scheduler::default();
+        let entity = Entity::new("e").id();
+        scheduler.every(entity, "tick", 0.5);
+
+        assert!(fired(&scheduler, 0.375).is_empty());
+        assert_eq!(fired(&scheduler, 0.25), [timer("tick", 1)]);
+
+        // Due again 0.5s after it first came due, not 0.5s after it was delivered
+        assert!(fired(&scheduler, 0.25).is_empty());
+        assert_eq!(fired(&scheduler, 0.125), [timer("tick", 1)]);
+    }
+
+    #[test]
+    fn long_frame_fires_once_with_the_missed_count() {
+        let scheduler = Scheduler::default();
+        let entity = Entity::new("e").id();
+        scheduler.every(entity, "tick", 0.25);
+        scheduler.every(entity, "fast", 0.);
+
+        // A zero interval is raised to MIN_INTERVAL, which is not exact in f32. It came due first
+        // so it is the most overdue.
+        let fired = fired(&scheduler, 10.);
+        assert_eq!(fired.len(), 2);
+        assert_eq!(fired[0].0, "fast");
+        assert!((9999..=10001).contains(&fired[0].1));
+        assert_eq!(fired[1], timer("tick", 40));
+    }
+
+    #[test]
+    fn most_overdue_timer_fires_first() {
+        let scheduler = Scheduler::default();
+        let entity = Entity::new("e").id();
+        scheduler.after(entity, "late", 0.5);
+        scheduler.after(entity, "early", 0.25);
+
+        assert_eq!(fired(&scheduler, 1.), [timer("early", 1), timer("late", 1)]);
+    }
+
+    #[test]
+    fn time_scale_slows_timers() {
+        let scheduler = Scheduler {
+            time_scale: 0.5,
+            ..Default::default()
+        };
+        let entity = Entity::new("e").id();
+        scheduler.after(entity, "once", 1.);
+
+        assert!(fired(&scheduler, 1.5).is_empty());
+        assert_eq!(fired(&scheduler, 0.5), [timer("once", 1)]);
+    }
+
+    #[test]
+    fn cancelled_and_orphaned_timers_do_not_fire() {
+        let scheduler = Scheduler::default();
+        let alive = Entity::new("alive").id();
+        let removed = Entity::new("removed").id();
+
+        let cancelled = scheduler.after(alive, "cancelled", 1.);
+        scheduler.after(removed, "orphaned", 1.);
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.cancel(cancelled));
+
+        let fired = scheduler.advance(2., |id| id == alive);
+        assert!(fired.is_empty());
+    }
+}
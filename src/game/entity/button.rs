@@ -1,143 +1,139 @@
 use crate::app::App;
 use crate::core::component::audio_clip::AudioClip;
 use crate::core::component::image::Image;
-use crate::core::component::Component;
-use crate::core::entity::{Entity, EntityFns};
-use crate::core::event::{Event, UpdateRenderablePayload};
-
+use crate::core::entity::behavior::EntityBehavior;
+use crate::core::entity::{Entity, EntityId};
+use crate::core::event::{Event, EventContext, UpdateRenderablePayload};
+use crate::core::scene::prefab::instantiate_prefab;
+use crate::core::scene::SceneBehavior;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use sfml::window::Event as SFMLEvent;
 
 use core::any::Any;
 
-#[derive(Default, Debug, Clone, Copy)]
-struct Data {
-    x: f32,
-    y: f32,
-    parent_offset: (f32, f32),
-    button_down: bool,
-}
-
-impl Component for Data {
-    fn get_name(&self) -> &str {
-        "data"
-    }
+pub static BUTTON_PREFAB: &str = "res/prefab/button.json";
 
-    fn as_any(&mut self) -> &mut dyn Any {
-        self
-    }
+/// Posted as a custom event when a button is pressed and released while under the cursor
+#[derive(Debug, Clone)]
+pub struct ButtonClicked {
+    pub id: EntityId,
+    pub name: String,
 }
 
-fn update_button(e: &mut Entity, _app: &App, _dt: f32) {
-    let data = *e.find_component::<Data>("data").unwrap();
-    let over_button;
-    {
-        let render_offset_x = data.parent_offset.0;
-        let render_offset_y = data.parent_offset.1;
-        let button = e.find_component::<Image>("background").unwrap();
-        let bx = render_offset_x + button.x;
-        let by = render_offset_y + button.y;
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Button {
+    /// Name of the AudioClip played while the button is held
+    sound: String,
 
-        let mx = data.x;
-        let my = data.y;
-        let half_width = button.width / 2.;
-        let half_height = button.height / 2.;
-        over_button = mx > bx - half_width
-            && mx < bx + half_width
-            && my > by - half_height
-            && my < by + half_height;
-
-        if over_button {
-            button.color.r = 0.;
-        } else {
-            button.color.r = 1.;
-        }
-    }
+    #[serde(skip)]
+    over_button: bool,
+    #[serde(skip)]
+    button_down: bool,
+}
 
-    if data.button_down && over_button {
+impl EntityBehavior for Button {
+    fn update(&mut self, e: &mut Entity, _app: &App, _dt: f32) {
         {
-            let beep = e.find_component::<AudioClip>("zombie").unwrap();
-            if beep.sound.get_sound().status() == sfml::audio::SoundStatus::STOPPED {
-                beep.sound.get_sound().play();
+            let button = e.find_component::<Image>("background").unwrap();
+            if self.over_button {
+                button.color.r = 0.;
+            } else {
+                button.color.r = 1.;
             }
         }
+
+        if self.button_down && self.over_button {
+            e.find_component::<AudioClip>(&self.sound).unwrap().play();
+        }
     }
-}
 
-#[allow(clippy::collapsible_match, clippy::single_match)]
-fn handle_event(e: &mut Entity, _app: &mut Option<&mut App>, ev: &Event) {
-    let data = e.find_component::<Data>("data").unwrap();
-    match ev {
-        Event::SFMLEvent(sev) => match sev {
-            SFMLEvent::MouseMoved { x, y } => {
-                data.x = *x as f32;
-                data.y = *y as f32;
+    fn event(
+        &mut self,
+        e: &mut Entity,
+        app: &mut Option<&mut App>,
+        ev: &Event,
+        ctx: &mut EventContext,
+    ) {
+        let left = Some(sfml::window::mouse::Button::Left);
+        match ev {
+            // Pointer events reach the whole subtree, only react to those aimed at this button
+            Event::PointerEnter(p) if p.entity == e.id() => self.over_button = true,
+            Event::PointerLeave(p) if p.entity == e.id() => self.over_button = false,
+            Event::PointerPressed(p) if p.entity == e.id() && p.button == left => {
+                // Only the topmost button under the cursor should react to the press
+                self.button_down = true;
+                ctx.stop_propagation();
             }
-            SFMLEvent::MouseButtonPressed { button, .. } => match button {
-                &sfml::window::mouse::Button::Left => {
-                    data.button_down = true;
-                }
-                _ => {}
-            },
-            SFMLEvent::MouseButtonReleased { button, .. } => match button {
-                &sfml::window::mouse::Button::Left => {
-                    data.button_down = false;
+            Event::PointerClick(p) if p.entity == e.id() && p.button == left => {
+                if let Some(a) = app {
+                    a.post_custom_event(ButtonClicked {
+                        id: e.id(),
+                        name: e.name.clone(),
+                    });
                 }
-                _ => {}
-            },
-            _ => {}
-        },
-        Event::UpdateRenderable(p) => {
-            let button = e.find_component::<Image>("background").unwrap();
-            match p {
-                UpdateRenderablePayload::X(x) => {
-                    button.x = *x;
-                }
-                UpdateRenderablePayload::MoveX(mx) => {
-                    button.x += *mx;
-                }
-                UpdateRenderablePayload::Y(y) => {
-                    button.y = *y;
-                }
-                UpdateRenderablePayload::MoveY(my) => {
-                    button.y += *my;
+            }
+            // Released anywhere, not just over the button
+            Event::SFMLEvent(SFMLEvent::MouseButtonReleased {
+                button: sfml::window::mouse::Button::Left,
+                ..
+            }) => {
+                self.button_down = false;
+            }
+            Event::UpdateRenderable(p) => {
+                let button = e.find_component::<Image>("background").unwrap();
+                match p {
+                    UpdateRenderablePayload::X(x) => {
+                        button.x = *x;
+                    }
+                    UpdateRenderablePayload::MoveX(mx) => {
+                        button.x += *mx;
+                    }
+                    UpdateRenderablePayload::Y(y) => {
+                        button.y = *y;
+                    }
+                    UpdateRenderablePayload::MoveY(my) => {
+                        button.y += *my;
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
+            _ => {}
         }
-        _ => {}
     }
-}
 
-fn prerender_button(e: &mut Entity, parent_offset: (f32, f32)) {
-    e.find_component::<Data>("data").unwrap().parent_offset = parent_offset;
-}
-
-pub fn make_button(app: &mut App) -> Entity {
-    let mut e = Entity::new(
-        "button",
-        EntityFns {
-            update_fn: update_button,
-            event_fn: handle_event,
-            prerender_fn: prerender_button,
-        },
-    );
-
-    e.add_component(Data::default());
+    fn describe(&self) -> Option<SceneBehavior> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(params)) => Some(SceneBehavior {
+                kind: "Button".to_string(),
+                params,
+            }),
+            _ => None,
+        }
+    }
 
-    {
-        let bg_image = app
-            .resource
-            .load_image_from_disk("res/img/button.png")
-            .unwrap();
-        let bg = Image::with_texture("background", &bg_image, 100., 100.);
-        e.add_component(bg);
+    fn checksum_state(&self) -> Option<Value> {
+        Some(json!({
+            "params": self.describe()?.params,
+            "over_button": self.over_button,
+            "button_down": self.button_down,
+        }))
     }
 
-    {
-        let audio_data = app.resource.load_audio_data("res/snd/zombie.wav").unwrap();
-        let zombie = AudioClip::new("zombie", audio_data);
-        e.add_component(zombie);
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
     }
+}
+
+/// Builds a Button from scene behavior parameters
+pub fn button_behavior(params: &Map<String, Value>) -> Result<Box<dyn EntityBehavior>, String> {
+    let button: Button =
+        serde_json::from_value(Value::Object(params.clone())).map_err(|e| e.to_string())?;
+    Ok(Box::new(button))
+}
 
-    e
+pub fn make_button(app: &mut App) -> Entity {
+    instantiate_prefab(app, BUTTON_PREFAB, Value::Null).unwrap()
 }
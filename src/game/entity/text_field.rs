@@ -0,0 +1,277 @@
+use crate::app::App;
+use crate::core::component::text::Text;
+use crate::core::entity::behavior::EntityBehavior;
+use crate::core::entity::{Entity, EntityId};
+use crate::core::event::{Event, EventContext, TextInput};
+use crate::core::resource::TextLoadInfo;
+use crate::core::scene::SceneBehavior;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sfml::window::{Event as SFMLEvent, Key};
+
+use core::any::Any;
+
+/// Posted as a custom event when Enter is pressed in a focused text field
+#[derive(Debug, Clone)]
+pub struct TextSubmitted {
+    pub id: EntityId,
+    pub name: String,
+    pub value: String,
+}
+
+/// Single line text entry, drawn with the Text component named "label"
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TextField {
+    value: String,
+
+    /// Shown while the field is empty and not focused
+    placeholder: String,
+
+    /// Longest value in characters, 0 for no limit
+    max_length: usize,
+
+    /// Cursor position in characters
+    #[serde(skip)]
+    cursor: usize,
+    #[serde(skip)]
+    focused: bool,
+}
+
+static LABEL_NAME: &str = "label";
+static CURSOR: char = '|';
+
+impl TextField {
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    fn apply(&mut self, input: &TextInput) {
+        let length = self.value.chars().count();
+        self.cursor = self.cursor.min(length);
+
+        match input {
+            TextInput::Insert(text) => {
+                let room = match self.max_length {
+                    0 => usize::MAX,
+                    max => max.saturating_sub(length),
+                };
+                let text: String = text.chars().take(room).collect();
+                let offset = self.byte_offset(self.cursor);
+                self.value.insert_str(offset, &text);
+                self.cursor += text.chars().count();
+            }
+            TextInput::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let offset = self.byte_offset(self.cursor);
+                self.value.remove(offset);
+            }
+            TextInput::Delete if self.cursor < length => {
+                let offset = self.byte_offset(self.cursor);
+                self.value.remove(offset);
+            }
+            TextInput::CursorLeft => self.cursor = self.cursor.saturating_sub(1),
+            TextInput::CursorRight => self.cursor = (self.cursor + 1).min(length),
+            TextInput::Home => self.cursor = 0,
+            TextInput::End => self.cursor = length,
+            _ => {}
+        }
+    }
+
+    /// Re-renders the label with the cursor drawn while focused
+    fn refresh(&self, e: &mut Entity, app: &mut App) {
+        let display = match (self.focused, self.value.is_empty()) {
+            (true, _) => {
+                let mut display = self.value.clone();
+                display.insert(self.byte_offset(self.cursor), CURSOR);
+                display
+            }
+            (false, true) => self.placeholder.clone(),
+            (false, false) => self.value.clone(),
+        };
+
+        let label = match e.find_component::<Text>(LABEL_NAME) {
+            Ok(label) => label,
+            Err(_) => return,
+        };
+
+        if display.is_empty() {
+            label.clear();
+            return;
+        }
+
+        // Every keystroke renders a new string, the label owns it so the last one is released
+        let info = TextLoadInfo {
+            text: display,
+            font_path: label.font_path.clone(),
+            font_size: label.font_size,
+        };
+        label.update_owned_text(app, &info);
+    }
+}
+
+impl EntityBehavior for TextField {
+    fn event(
+        &mut self,
+        e: &mut Entity,
+        app: &mut Option<&mut App>,
+        ev: &Event,
+        ctx: &mut EventContext,
+    ) {
+        let a = match app {
+            Some(a) => a,
+            None => return,
+        };
+
+        match ev {
+            Event::FocusGained(id) if *id == e.id() => {
+                self.focused = true;
+                self.cursor = self.value.chars().count();
+                self.refresh(e, a);
+            }
+            Event::FocusLost(id) if *id == e.id() => {
+                self.focused = false;
+                self.refresh(e, a);
+            }
+            Event::TextInput(p) if p.entity == e.id() => {
+                match &p.input {
+                    TextInput::Submit => {
+                        a.post_custom_event(TextSubmitted {
+                            id: e.id(),
+                            name: e.name.clone(),
+                            value: self.value.clone(),
+                        });
+                        a.set_focus(None);
+                    }
+                    input => {
+                        self.apply(input);
+                        self.refresh(e, a);
+                    }
+                }
+                ctx.stop_propagation();
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed {
+                code: Key::Escape, ..
+            }) if self.focused => {
+                a.set_focus(None);
+                ctx.stop_propagation();
+            }
+            // Typing must not trigger the key bindings of the rest of the tree
+            Event::SFMLEvent(SFMLEvent::KeyPressed { .. } | SFMLEvent::TextEntered { .. })
+                if self.focused =>
+            {
+                ctx.stop_propagation();
+            }
+            _ => {}
+        }
+    }
+
+    fn describe(&self) -> Option<SceneBehavior> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(params)) => Some(SceneBehavior {
+                kind: "TextField".to_string(),
+                params,
+            }),
+            _ => None,
+        }
+    }
+
+    fn checksum_state(&self) -> Option<Value> {
+        Some(json!({
+            "params": self.describe()?.params,
+            "cursor": self.cursor,
+            "focused": self.focused,
+        }))
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Builds a TextField from scene behavior parameters
+pub fn text_field_behavior(params: &Map<String, Value>) -> Result<Box<dyn EntityBehavior>, String> {
+    let field: TextField =
+        serde_json::from_value(Value::Object(params.clone())).map_err(|e| e.to_string())?;
+    Ok(Box::new(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(value: &str, cursor: usize) -> TextField {
+        TextField {
+            value: value.to_string(),
+            cursor,
+            ..Default::default()
+        }
+    }
+
+    fn insert(text: &str) -> TextInput {
+        TextInput::Insert(text.to_string())
+    }
+
+    #[test]
+    fn insert_counts_the_cursor_in_characters() {
+        let mut f = field("", 0);
+        f.apply(&insert("Ωé"));
+        assert_eq!(f.cursor, 2);
+
+        f.apply(&TextInput::CursorLeft);
+        f.apply(&insert("a"));
+        assert_eq!(f.value, "Ωaé");
+        assert_eq!(f.cursor, 2);
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_whole_characters() {
+        let mut f = field("aΩ€b", 2);
+        f.apply(&TextInput::Backspace);
+        assert_eq!(f.value, "a€b");
+        assert_eq!(f.cursor, 1);
+
+        f.apply(&TextInput::Delete);
+        assert_eq!(f.value, "ab");
+        assert_eq!(f.cursor, 1);
+    }
+
+    #[test]
+    fn editing_at_the_ends_does_nothing() {
+        let mut f = field("Ω", 0);
+        f.apply(&TextInput::Backspace);
+        f.apply(&TextInput::CursorLeft);
+        assert_eq!((f.value.as_str(), f.cursor), ("Ω", 0));
+
+        f.apply(&TextInput::End);
+        f.apply(&TextInput::Delete);
+        f.apply(&TextInput::CursorRight);
+        assert_eq!((f.value.as_str(), f.cursor), ("Ω", 1));
+    }
+
+    #[test]
+    fn cursor_past_the_end_is_clamped() {
+        let mut f = field("éé", 10);
+        f.apply(&insert("!"));
+        assert_eq!(f.value, "éé!");
+        assert_eq!(f.cursor, 3);
+    }
+
+    #[test]
+    fn insert_is_cut_at_the_max_length() {
+        let mut f = TextField {
+            max_length: 3,
+            ..field("Ω", 1)
+        };
+        f.apply(&insert("ééé"));
+        assert_eq!(f.value, "Ωéé");
+        assert_eq!(f.cursor, 3);
+
+        f.apply(&insert("x"));
+        assert_eq!(f.value, "Ωéé");
+    }
+}
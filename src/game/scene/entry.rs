@@ -1,12 +1,141 @@
 use crate::{
     app::App,
-    core::entity::{Entity, EntityFns},
+    core::{
+        entity::{behavior::ClosureBehavior, Entity},
+        event::{Event, UpdateRenderablePayload},
+        event_queue::{EventPriority, EventTarget},
+        input::action::{ActionBindings, Binding},
+        renderer::uniform::UniformValue,
+        scene::load_scene,
+    },
+    game::entity::{
+        button::{button_behavior, ButtonClicked},
+        text_field::{text_field_behavior, TextSubmitted},
+    },
 };
 
+use sfml::window::{Event as SFMLEvent, Key};
+
 use super::testbed::make_testbed;
 
+static INPUT_BINDINGS: &str = "res/input/bindings.json";
+static SAVED_BINDINGS_PATH: &str = "bindings.json";
+static TEST_BUTTON_PATH: &str = "entry/testbed/test_button";
+
+/// Joystick id used by the simulated controller, SFML only reports ids 0-7 for real ones
+static SIMULATED_GAMEPAD: u32 = 8;
+
+/// Post effects toggled with F1 to F4 and F6
+static POST_EFFECTS: [(Key, &str); 5] = [
+    (Key::F1, "vignette"),
+    (Key::F2, "crt"),
+    (Key::F3, "blur"),
+    (Key::F4, "bloom"),
+    (Key::F6, "color_grade"),
+];
+
 pub fn make_entry(app: &mut App) -> Entity {
-    let mut e = Entity::new("entry", EntityFns::default());
+    app.behaviors.register("Button", button_behavior);
+    app.behaviors.register("TextField", text_field_behavior);
+
+    match ActionBindings::load(INPUT_BINDINGS) {
+        Ok(bindings) => app.actions.set_bindings(bindings),
+        Err(err) => {
+            println!("Failed to load input bindings, using the defaults: {}", err);
+            app.actions.set_bindings(default_bindings());
+        }
+    }
+
+    add_post_effects(app);
+
+    let mut e = Entity::with_behavior(
+        "entry",
+        ClosureBehavior::new().on_event(|_e, app, ev, _ctx| match ev {
+            Event::SFMLEvent(SFMLEvent::Closed) => {
+                if let Some(a) = app {
+                    a.close_window();
+                }
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed { code: Key::J, .. }) => {
+                // Toggle a simulated controller so gamepads can be exercised without hardware
+                if let Some(a) = app {
+                    let joystickid = SIMULATED_GAMEPAD;
+                    a.inject_window_event(match a.gamepads.is_connected(joystickid) {
+                        true => SFMLEvent::JoystickDisconnected { joystickid },
+                        false => SFMLEvent::JoystickConnected { joystickid },
+                    });
+                }
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed { code: Key::R, .. }) => {
+                // Invert the horizontal controls at runtime and save them, like a controls menu
+                // would
+                if let Some(a) = app {
+                    let mut bindings = a.actions.bindings().clone();
+                    let move_x = bindings.axes.get("move_x").cloned().unwrap_or_default();
+                    bindings.unbind("move_x");
+                    for b in move_x {
+                        bindings.bind_axis("move_x", b.binding, -b.scale);
+                    }
+                    if let Err(err) = bindings.save(SAVED_BINDINGS_PATH) {
+                        println!("Failed to save input bindings: {}", err);
+                    }
+                    a.actions.set_bindings(bindings);
+                }
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed { code: Key::H, .. }) => {
+                // Paths reach into the first testbed without a handle on it, the move is
+                // delivered with the queued events of the next frame
+                if let Some(a) = app {
+                    a.post_event_to(
+                        "entry/testbed/test-animated",
+                        Event::UpdateRenderable(UpdateRenderablePayload::MoveY(-10.)),
+                    );
+                }
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed {
+                code: code @ (Key::F1 | Key::F2 | Key::F3 | Key::F4 | Key::F6),
+                ..
+            }) => {
+                if let (Some(a), Some((_, effect))) =
+                    (app, POST_EFFECTS.iter().find(|(key, _)| key == code))
+                {
+                    let enabled = a.renderer.post.toggle(effect);
+                    println!("Post effect {}: {}", effect, enabled);
+                }
+            }
+            Event::SFMLEvent(SFMLEvent::KeyPressed { code: Key::I, .. }) => {
+                // Resolve the first testbed's button by path and nudge it by id
+                if let Some(a) = app {
+                    match a.find_entity_id(TEST_BUTTON_PATH) {
+                        Ok(id) => a.post_event_with_priority(
+                            EventTarget::Entity(id),
+                            Event::UpdateRenderable(UpdateRenderablePayload::MoveX(10.)),
+                            EventPriority::Normal,
+                        ),
+                        Err(err) => println!("Failed to find {}: {:?}", TEST_BUTTON_PATH, err),
+                    }
+                }
+            }
+            Event::GamepadConnected(id) => println!("Gamepad {} connected", id),
+            Event::GamepadDisconnected(id) => println!("Gamepad {} disconnected", id),
+            Event::Custom(custom) => {
+                if let Some(clicked) = custom.downcast_ref::<ButtonClicked>() {
+                    let path = app
+                        .as_ref()
+                        .and_then(|a| a.find_entity_path(clicked.id).ok())
+                        .unwrap_or(&clicked.name);
+                    println!("Button {} ({:?}) clicked", path, clicked.id);
+                }
+                if let Some(submitted) = custom.downcast_ref::<TextSubmitted>() {
+                    println!(
+                        "{} ({:?}) submitted: {}",
+                        submitted.name, submitted.id, submitted.value
+                    );
+                }
+            }
+            _ => {}
+        }),
+    );
 
     // Testbed(s)
     let num_scenes = 5;
@@ -14,5 +143,67 @@ pub fn make_entry(app: &mut App) -> Entity {
         e.add_child(make_testbed(app, (1. / num_scenes as f32) * ts as f32));
     }
 
+    // Data driven overlay
+    match load_scene(app, "res/scene/overlay.json") {
+        Ok(overlay) => e.add_child(overlay),
+        Err(err) => println!("Failed to load overlay scene: {}", err),
+    }
+
     e
 }
+
+/// Keyboard only bindings used when the bindings config can not be loaded
+fn default_bindings() -> ActionBindings {
+    let mut bindings = ActionBindings::default();
+    bindings.bind("move_up", Binding::Key { key: Key::W });
+    bindings.bind("move_down", Binding::Key { key: Key::S });
+    bindings.bind("run", Binding::Key { key: Key::LShift });
+    bindings.bind("quit", Binding::Key { key: Key::Q });
+    bindings.bind_axis("move_x", Binding::Key { key: Key::A }, -1.);
+    bindings.bind_axis("move_x", Binding::Key { key: Key::D }, 1.);
+    bindings
+}
+
+fn add_post_effects(app: &mut App) {
+    use UniformValue::*;
+
+    let effects: [(&str, &[(&str, UniformValue)]); 5] = [
+        (
+            "vignette",
+            &[("radius", Float(0.3)), ("strength", Float(0.8))],
+        ),
+        (
+            "crt",
+            &[("intensity", Float(0.4)), ("flicker", Float(0.03))],
+        ),
+        ("blur", &[("radius", Float(1.5))]),
+        (
+            "bloom",
+            &[
+                ("threshold", Float(0.6)),
+                ("radius", Float(3.)),
+                ("intensity", Float(2.)),
+            ],
+        ),
+        (
+            "color_grade",
+            &[
+                ("tint", Vec3([1.1, 1., 0.85])),
+                ("saturation", Float(0.6)),
+                ("contrast", Float(1.2)),
+            ],
+        ),
+    ];
+
+    for (name, uniforms) in effects {
+        let path = format!("res/glsl/post/{}.glsl", name);
+        match app.renderer.post.add_effect(name, &path) {
+            Ok(effect) => {
+                for (uniform, value) in uniforms {
+                    effect.set_uniform(uniform, *value);
+                }
+            }
+            Err(err) => println!("Failed to add post effect {}: {}", name, err),
+        }
+    }
+}
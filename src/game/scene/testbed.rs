@@ -1,187 +1,265 @@
 use crate::app::App;
 use crate::core::component::audio_clip::AudioClip;
-use crate::core::component::image::Image;
-use crate::core::component::offset::{Offset, OFFSET_NAME};
+use crate::core::component::draggable::Draggable;
+use crate::core::component::drop_target::DropTarget;
+use crate::core::component::image::{Image, ImageRenderType};
+use crate::core::component::pickable::Pickable;
+use crate::core::component::render_target::{RedrawMode, RenderTarget};
 use crate::core::component::text::Text;
-use crate::core::component::Component;
+use crate::core::component::transform::Transform;
 use crate::core::entity::animated_image::{
-    animated_image_add_animation, animated_image_get_position, animated_image_set_animation,
-    make_animated_image,
+    animated_image_get_position, animated_image_set_animation, make_animated_image,
 };
-use crate::core::entity::{Entity, EntityFns, RenderableEntity};
-use crate::core::event::Event;
-
+use crate::core::entity::behavior::{ClosureBehavior, EntityBehavior};
+use crate::core::entity::query::Query;
+use crate::core::entity::{Entity, RenderableEntity};
+use crate::core::event::{Event, EventContext};
+use crate::core::input::gamepad::{GamepadAxis, GamepadButton};
+
+use crate::core::renderer::atlas::ATLAS_PAGE_SIZE;
+use crate::core::renderer::material::MaterialUniform;
+use crate::core::renderer::uniform::UniformValue;
 use crate::core::resource::{AsyncLoadHandle, TextLoadInfo};
-use crate::game::entity::button::make_button;
+use crate::core::scene::prefab::instantiate_prefab;
+use crate::core::scene::save_scene;
+use crate::core::scheduler::TimerId;
+use crate::game::entity::button::{make_button, BUTTON_PREFAB};
 use crate::util::alpha::Alpha;
+use crate::util::rect::Rect;
 
 use rand::Rng;
+use serde_json::{json, Value};
+use sfml::window::mouse::Button;
 use sfml::window::{Event as SFMLEvent, Key};
 
 use core::any::Any;
 use std::f32::consts::PI;
 
 #[derive(Default, Debug, Clone, Copy)]
-struct Data {
+struct Testbed {
     counter: f32,
-    left_down: bool,
-    right_down: bool,
-    parent_offset: (f32, f32),
+
+    /// Offset dragged with the right mouse button
+    pan: (f32, f32),
     time_scale: f32,
 
     sync_loaded_texture_id: u32,
     async_local_handle: Option<AsyncLoadHandle>,
     async_remote_handle: Option<AsyncLoadHandle>,
-}
-
-impl Component for Data {
-    fn get_name(&self) -> &str {
-        "data"
-    }
+    beep_timer: Option<TimerId>,
 
-    fn as_any(&mut self) -> &mut dyn Any {
-        self
-    }
+    /// Stops the testbed until the "unpause" timer fires
+    paused: bool,
 }
 
 static REMOTE_IMAGE_URL: &str = "http://wuteri.ch/img/Teleport.jpg";
 static DISK_IMAGE_PATH: &str = "res/img/motorcycle.png";
 static DISK_IMAGE_QUAD: &str = "res/img/test-clip.png";
-static DISK_IMAGE_MARIO: &str = "res/img/mario.png";
+static MARIO_PREFAB: &str = "res/prefab/mario.json";
+static MARIO_SHEET: &str = "res/img/mario.png";
+static PREVIEW_SIZE: u32 = 256;
+static BEEP_INTERVAL: f32 = 1.;
+static PAUSE_DURATION: f32 = 2.;
+static DISSOLVE_SHADER: &str = "res/glsl/material/dissolve.glsl";
+static OUTLINE_SHADER: &str = "res/glsl/material/outline.glsl";
+static WAVE_SHADER: &str = "res/glsl/material/wave.glsl";
+
+impl EntityBehavior for Testbed {
+    fn update(&mut self, e: &mut Entity, app: &App, in_dt: f32) {
+        //println!("fps: {}", 1. / dt);
+
+        // Holding the left shoulder of any controller plays the testbed in slow motion
+        let slow_motion = match app.gamepads.any_pressed(GamepadButton::LeftShoulder) {
+            true => 0.25,
+            false => 1.,
+        };
+        let dt = match self.paused {
+            true => 0.,
+            false => in_dt * self.time_scale * slow_motion,
+        };
+        self.counter += dt;
+        let d = *self;
+
+        {
+            let title = e.find_component::<Text>("title").unwrap();
+            title.rotation -= dt;
+        }
 
-fn update_testbed(e: &mut Entity, app: &App, in_dt: f32) {
-    //println!("fps: {}", 1. / dt);
+        {
+            let card = e.find_child_by_name("card").unwrap();
+            let card = card.find_component::<Image>("card").unwrap();
+            card.rotation += dt * 4.;
+
+            // Dissolves the card away and back every few seconds
+            if let Some(material) = &mut card.material {
+                let threshold = (d.counter * 0.5).sin().abs();
+                material.set_uniform(
+                    "threshold",
+                    MaterialUniform::Value(UniformValue::Float(threshold)),
+                );
+            }
+        }
 
-    let d;
-    let dt;
-    {
-        let data = e.find_component::<Data>("data").unwrap();
-        dt = in_dt * data.time_scale;
-        data.counter += dt;
-        d = *data;
-    }
+        {
+            let button = e.find_by_path("test_button").unwrap();
+            button.set_x(d.counter.cos() * 50. * PI * 2. + 300.);
+            button.set_y(d.counter.sin() * 50. * PI * 2. + 300.);
+        }
 
-    {
-        let title = e.find_component::<Text>("title").unwrap();
-        title.rotation -= dt;
-    }
+        if app.input.just_pressed(Button::Right) {
+            println!("Panning from {:?}", app.input.mouse_position());
+        }
+        if app.input.is_down(Button::Right) {
+            let delta = app.input.mouse_delta();
+            self.pan.0 += delta.0 as f32;
+            self.pan.1 += delta.1 as f32;
+        }
+        if app.input.just_released(Button::Right) {
+            println!("Panned the testbed to {:?}", self.pan);
+        }
 
-    {
-        let card = e.find_component::<Image>("card").unwrap();
-        card.rotation += dt * 4.;
-    }
+        {
+            let transform = e.get_mut::<Transform>().unwrap();
+            transform.x = d.counter.cos() * 10. * PI * 2. + self.pan.0;
+            transform.y = d.counter.sin() * 10. * PI * 2. + self.pan.1;
 
-    {
-        let beep = e.find_component::<AudioClip>("beep").unwrap();
-        if beep.sound.get_sound().status() == sfml::audio::SoundStatus::STOPPED {
-            beep.sound.get_sound().play();
-        }
-    }
-    {
-        let button = e.find_child_by_name("test_button").unwrap();
-        button.set_x(d.counter.cos() * 50. * PI * 2. + 300.);
-        button.set_y(d.counter.sin() * 50. * PI * 2. + 300.);
-    }
+            // Scrolling turns the testbed like Comma and Period do
+            transform.rotation -= app.input.wheel_delta().0 * 0.1;
 
-    {
-        let offset = e.find_component::<Offset>(OFFSET_NAME).unwrap();
-        offset.x = d.counter.cos() * 10. * PI * 2.;
-        offset.y = d.counter.sin() * 10. * PI * 2.;
-    }
-    {
-        let mario = e.find_child_by_name("test-animated").unwrap();
-        if mario.active {
-            match (d.left_down, d.right_down) {
-                (true, false) => {
-                    mario.move_x(-100. * dt);
+            // The right stick of every controller spins the testbed
+            for pad in app.gamepads.connected() {
+                transform.rotation += app.gamepads.axis(pad.id(), GamepadAxis::RightX) * dt;
+            }
+        }
+        {
+            let mario = e.find_child_by_name("test-animated").unwrap();
+            if mario.active {
+                let move_x = app.actions.axis("move_x");
+                if move_x != 0. {
+                    let speed = match app.actions.is_pressed("run") {
+                        true => 200.,
+                        false => 100.,
+                    };
+                    mario.move_x(speed * move_x * dt);
                 }
-                (false, true) => {
-                    mario.move_x(100. * dt);
+                let mario_location = animated_image_get_position(mario);
+                if mario_location.1 < app.renderer.viewport.window_size.1 / 2. {
+                    mario.zindex = -1;
+                } else {
+                    mario.zindex = 1;
                 }
-                _ => {}
-            }
-            let mario_location = animated_image_get_position(mario);
-            if mario_location.1 < app.renderer.viewport.window_size.1 / 2. {
-                mario.zindex = -1;
-            } else {
-                mario.zindex = 1;
             }
         }
     }
-}
 
-fn handle_event(e: &mut Entity, app: &mut Option<&mut App>, ev: &Event) {
-    match ev {
-        Event::SFMLEvent(ev) => match ev {
-            SFMLEvent::Closed => {
-                app.as_mut().unwrap().close_window();
-            }
-            SFMLEvent::MouseMoved { x, y } => {
-                let offset = e.find_component::<Data>("data").unwrap().parent_offset;
-                let card = e.find_component::<Image>("card").unwrap();
-                card.x = *x as f32 - offset.0;
-                card.y = *y as f32 - offset.1;
-            }
-            SFMLEvent::KeyPressed { code, .. } => match *code {
-                Key::W => {
-                    let animated_image = e.find_child_by_name("test-animated").unwrap();
-                    if animated_image.active {
-                        animated_image.move_y(-10.);
-                    }
-                }
-                Key::A => {
-                    {
-                        let animated_image = e.find_child_by_name("test-animated").unwrap();
-                        if animated_image.active {
-                            animated_image_set_animation(animated_image, "walking");
-                            animated_image.set_scale_x(-3.);
+    fn event(
+        &mut self,
+        e: &mut Entity,
+        app: &mut Option<&mut App>,
+        ev: &Event,
+        _ctx: &mut EventContext,
+    ) {
+        match ev {
+            Event::SFMLEvent(SFMLEvent::KeyPressed { code, .. }) => match *code {
+                Key::P => {
+                    // Toggle the repeating beep
+                    if let Some(a) = app {
+                        match self.beep_timer.take() {
+                            Some(timer) => {
+                                a.scheduler.cancel(timer);
+                            }
+                            None => {
+                                self.beep_timer =
+                                    Some(a.scheduler.every(e.id(), "beep", BEEP_INTERVAL));
+                            }
                         }
                     }
-                    {
-                        let data = e.find_component::<Data>("data").unwrap();
-                        data.left_down = true;
-                    }
                 }
-                Key::S => {
-                    let animated_image = e.find_child_by_name("test-animated").unwrap();
-                    if animated_image.active {
-                        animated_image_set_animation(animated_image, "swim");
-                        animated_image.move_y(10.);
-                    }
-                }
-                Key::D => {
-                    {
-                        let animated_image = e.find_child_by_name("test-animated").unwrap();
-                        if animated_image.active {
-                            animated_image_set_animation(animated_image, "walking");
-                            animated_image.set_scale_x(3.);
-                        }
-                    }
-                    {
-                        let data = e.find_component::<Data>("data").unwrap();
-                        data.right_down = true;
+                Key::X => {
+                    // Freeze this testbed for a moment
+                    if let (Some(a), false) = (app, self.paused) {
+                        self.paused = true;
+                        a.scheduler.after(e.id(), "unpause", PAUSE_DURATION);
                     }
                 }
                 Key::U => {
                     if let Some(a) = app {
                         let info = a.resource.load_image_from_disk(DISK_IMAGE_PATH).unwrap();
-                        let mut dynamic_cmp = Image::default();
-                        let mut thread_rng = rand::thread_rng();
+                        let mut dynamic_cmp = Image::new("dynamic");
                         dynamic_cmp.texture = Some(info);
-                        dynamic_cmp.x = thread_rng.gen_range(0f32..1000f32);
-                        dynamic_cmp.y = thread_rng.gen_range(0f32..1000f32);
+                        dynamic_cmp.x = a.rng.gen_range(0f32..1000f32);
+                        dynamic_cmp.y = a.rng.gen_range(0f32..1000f32);
                         dynamic_cmp.width = info.width as f32;
                         dynamic_cmp.height = info.height as f32;
-                        dynamic_cmp.color.r = thread_rng.gen_range(0f32..1f32);
-                        dynamic_cmp.color.g = thread_rng.gen_range(0f32..1f32);
-                        dynamic_cmp.color.b = thread_rng.gen_range(0f32..1f32);
-                        e.add_component(dynamic_cmp);
+                        dynamic_cmp.color.r = a.rng.gen_range(0f32..1f32);
+                        dynamic_cmp.color.g = a.rng.gen_range(0f32..1f32);
+                        dynamic_cmp.color.b = a.rng.gen_range(0f32..1f32);
+                        a.commands.add_component(e.id(), dynamic_cmp);
                     }
                 }
-                Key::Q => {
-                    app.as_mut().unwrap().close_window();
+                Key::T => {
+                    // Hide or show every button in this testbed
+                    Query::new()
+                        .tag("button")
+                        .with::<Image>()
+                        .for_each(e, &mut |b| {
+                            b.visible = !b.visible;
+                        });
+                }
+                Key::Y => {
+                    if let Some(a) = app {
+                        a.commands.remove_component(e.id(), "dynamic");
+                    }
+                }
+                Key::B => {
+                    if let Some(a) = app {
+                        let mut button = instantiate_prefab(
+                            a,
+                            BUTTON_PREFAB,
+                            json!({
+                                "name": "spawned_button",
+                                "components": [
+                                    { "type": "Image", "name": "background", "alpha": 0.5 }
+                                ]
+                            }),
+                        )
+                        .unwrap();
+                        button.add_tag("spawned");
+                        a.commands.spawn(e.id(), button);
+                    }
+                }
+                Key::N => {
+                    // Despawn every spawned button, including those moved under mario
+                    if let Some(a) = app {
+                        for id in Query::new().tag("spawned").ids(e) {
+                            a.commands.despawn(id);
+                        }
+                    }
+                }
+                Key::G => {
+                    // Tint the Images of unlabelled Entities, buttons keep their hover tint
+                    Query::new()
+                        .without::<Text>()
+                        .without_tag("button")
+                        .for_each_component::<Image>(e, &mut |_, image| {
+                            image.color.g = 1. - image.color.g;
+                        });
+                }
+                Key::M => {
+                    if let Some(a) = app {
+                        let mario_id = e.find_child_by_name("test-animated").unwrap().id();
+                        if let Ok(button) = e.find_child_by_name("spawned_button") {
+                            a.commands.reparent(button.id(), mario_id);
+                        }
+                    }
+                }
+                Key::F5 => {
+                    if let Some(a) = app {
+                        if let Err(err) = save_scene(&a.resource, e, "testbed.json") {
+                            println!("Failed to save testbed scene: {}", err);
+                        }
+                    }
                 }
-
                 Key::LBracket => {
                     let animated_image = e.find_child_by_name("test-animated").unwrap();
                     animated_image.active = false;
@@ -196,6 +274,28 @@ fn handle_event(e: &mut Entity, app: &mut Option<&mut App>, ev: &Event) {
                     animated_image.set_alpha(1.);
                 }
 
+                Key::K => {
+                    for text in e.get_all::<Text>() {
+                        println!("Text {}: {:?}", text.name, text.text);
+                    }
+                }
+
+                Key::O => {
+                    for tester in e.get_all_mut::<Text>().filter(|t| t.name == "tester") {
+                        tester.color.r = 1. - tester.color.r;
+                    }
+                }
+
+                Key::Comma => {
+                    let transform = e.get_mut::<Transform>().unwrap();
+                    transform.rotation -= 0.1;
+                }
+
+                Key::Period => {
+                    let transform = e.get_mut::<Transform>().unwrap();
+                    transform.rotation += 0.1;
+                }
+
                 Key::Num9 => {
                     let animated_image = e.find_child_by_name("test-animated").unwrap();
                     animated_image.visible = false;
@@ -221,88 +321,120 @@ fn handle_event(e: &mut Entity, app: &mut Option<&mut App>, ev: &Event) {
                 }
                 _ => {}
             },
-            SFMLEvent::KeyReleased { code, .. } => match *code {
-                Key::A => {
-                    {
-                        let animated_image = e.find_child_by_name("test-animated").unwrap();
-                        if animated_image.active {
-                            animated_image_set_animation(animated_image, "idle");
-                        }
-                    }
-
-                    {
-                        let data = e.find_component::<Data>("data").unwrap();
-                        data.left_down = false;
+            Event::ActionPressed(action) => match action.as_str() {
+                "move_up" => {
+                    let animated_image = e.find_child_by_name("test-animated").unwrap();
+                    if animated_image.active {
+                        animated_image.move_y(-10.);
                     }
                 }
-                Key::D => {
-                    {
-                        let animated_image = e.find_child_by_name("test-animated").unwrap();
-                        if animated_image.active {
-                            animated_image_set_animation(animated_image, "idle");
-                        }
-                    }
-
-                    {
-                        let data = e.find_component::<Data>("data").unwrap();
-                        data.right_down = false;
+                "move_down" => {
+                    let animated_image = e.find_child_by_name("test-animated").unwrap();
+                    if animated_image.active {
+                        animated_image_set_animation(animated_image, "swim");
+                        animated_image.move_y(10.);
                     }
                 }
+                "quit" => {
+                    app.as_mut().unwrap().close_window();
+                }
                 _ => {}
             },
-            _ => {}
-        },
-        Event::ImageLoadEvent(img_data) => {
-            let data = *e.find_component::<Data>("data").unwrap();
-            let handle_id = img_data.handle.id;
-
-            if let Some(async_handle) = data.async_remote_handle {
-                if async_handle.id == handle_id {
-                    let card = e.find_component::<Image>("card").unwrap();
-                    card.apply_image(img_data);
+            Event::ActionReleased(action) if action == "move_down" => {
+                let moving = app.as_ref().is_some_and(|a| a.actions.axis("move_x") != 0.);
+                let animated_image = e.find_child_by_name("test-animated").unwrap();
+                if animated_image.active && !moving {
+                    animated_image_set_animation(animated_image, "idle");
+                }
+            }
+            Event::ActionAxis(payload) if payload.axis == "move_x" => {
+                let animated_image = e.find_child_by_name("test-animated").unwrap();
+                if animated_image.active {
+                    if payload.value < 0. {
+                        animated_image_set_animation(animated_image, "walking");
+                        animated_image.set_scale_x(-3.);
+                    } else if payload.value > 0. {
+                        animated_image_set_animation(animated_image, "walking");
+                        animated_image.set_scale_x(3.);
+                    } else {
+                        animated_image_set_animation(animated_image, "idle");
+                    }
                 }
             }
+            Event::Timer(timer) if Some(timer.id) == self.beep_timer => {
+                if timer.count > 1 {
+                    println!("Fell behind by {} beeps", timer.count - 1);
+                }
+                e.find_component::<AudioClip>("beep").unwrap().play();
+            }
+            Event::Timer(timer) if timer.name == "unpause" => {
+                self.paused = false;
+            }
+            Event::ImageLoadEvent(img_data) => {
+                let data = *self;
+                let handle_id = img_data.handle.id;
+
+                if let Some(async_handle) = data.async_remote_handle {
+                    if async_handle.id == handle_id {
+                        let card = e.find_child_by_name("card").unwrap();
+                        let card = card.find_component::<Image>("card").unwrap();
+                        card.apply_image(img_data);
+                    }
+                }
 
-            if let Some(async_handle) = data.async_local_handle {
-                if async_handle.id == handle_id {
-                    assert!(
-                        img_data.texture_id == data.sync_loaded_texture_id,
-                        "Expect async to return sync'd loaded texture"
-                    );
-                    let async_local = e.find_component::<Image>("async_local").unwrap();
-                    async_local.apply_image(img_data)
+                if let Some(async_handle) = data.async_local_handle {
+                    if async_handle.id == handle_id {
+                        assert!(
+                            img_data.texture_id == data.sync_loaded_texture_id,
+                            "Expect async to return sync'd loaded texture"
+                        );
+                        let async_local = e.find_component::<Image>("async_local").unwrap();
+                        async_local.apply_image(img_data)
+                    }
                 }
             }
+            Event::WindowUpdated(config) => {
+                let quad = e.find_child_by_name("test-quad").unwrap();
+                quad.set_x((config.width / 2) as f32);
+                quad.set_y((config.height / 2) as f32);
+            }
+            _ => {}
         }
-        Event::WindowUpdated(config) => {
-            let quad = e.find_child_by_name("test-quad").unwrap();
-            quad.set_x((config.width / 2) as f32);
-            quad.set_y((config.height / 2) as f32);
-        }
-        _ => {}
     }
-}
 
-fn prerender_testbed(e: &mut Entity, parent_offset: (f32, f32)) {
-    e.find_component::<Data>("data").unwrap().parent_offset = parent_offset;
+    fn checksum_state(&self) -> Option<Value> {
+        Some(json!({
+            "counter": self.counter,
+            "pan": self.pan,
+            "time_scale": self.time_scale,
+            "beep_timer": self.beep_timer.is_some(),
+            "paused": self.paused,
+        }))
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 pub fn make_testbed(app: &mut App, time_scale: f32) -> Entity {
-    let mut e = Entity::new(
-        "testbed",
-        EntityFns {
-            update_fn: update_testbed,
-            event_fn: handle_event,
-            prerender_fn: prerender_testbed,
-        },
-    );
-
-    let mut data = Data {
+    let mut e = Entity::new("testbed");
+
+    let mut data = Testbed {
         time_scale,
         ..Default::default()
     };
 
-    e.add_component(Offset::default());
+    {
+        // Rotate the whole testbed around the center of the window
+        e.add_component(Transform {
+            pivot: (
+                app.renderer.viewport.window_size.0 / 2.,
+                app.renderer.viewport.window_size.1 / 2.,
+            ),
+            ..Default::default()
+        });
+    }
 
     {
         let texture_info = app
@@ -354,7 +486,60 @@ pub fn make_testbed(app: &mut App, time_scale: f32) -> Entity {
             .resource
             .load_image_from_url_async(REMOTE_IMAGE_URL)
             .ok();
-        e.add_component(Image::new("card"));
+
+        // Dragged around the window and dropped on the test quad
+        let mut card = Entity::with_behavior(
+            "card",
+            ClosureBehavior::new().on_event(|e, _app, ev, _ctx| match ev {
+                Event::DragStart(p) if p.entity == e.id() => set_card_alpha(e, 0.5),
+                Event::DragEnd(p) if p.entity == e.id() => {
+                    set_card_alpha(e, 1.);
+                    if p.accepted {
+                        println!("Card dropped at {:?}", p.position);
+                    }
+                }
+                _ => {}
+            }),
+        );
+        card.add_component(Transform {
+            x: 300.,
+            y: 600.,
+            ..Default::default()
+        });
+        card.add_component(Pickable::default());
+        card.add_component(Draggable {
+            payload: "card".to_string(),
+            bounds: Some(Rect {
+                x: 0.,
+                y: 0.,
+                w: app.renderer.viewport.window_size.0,
+                h: app.renderer.viewport.window_size.1,
+            }),
+            ..Default::default()
+        });
+        let mut image = Image::new("card");
+        image.material = app
+            .resource
+            .load_material(None, DISSOLVE_SHADER)
+            .map_err(|err| println!("Failed to load card material: {}", err))
+            .ok();
+        if let Some(material) = &mut image.material {
+            material
+                .set_uniform(
+                    "noise_scale",
+                    MaterialUniform::Value(UniformValue::Float(48.)),
+                )
+                .set_uniform(
+                    "edge_width",
+                    MaterialUniform::Value(UniformValue::Float(0.05)),
+                )
+                .set_uniform(
+                    "edge_color",
+                    MaterialUniform::Value(UniformValue::Vec4([1., 0.6, 0.1, 1.])),
+                );
+        }
+        card.add_component(image);
+        e.add_child(card);
     }
 
     {
@@ -370,6 +555,9 @@ pub fn make_testbed(app: &mut App, time_scale: f32) -> Entity {
 
         image.set_x(app.renderer.viewport.window_size.0 / 2.);
         image.set_y(app.renderer.viewport.window_size.1 / 2.);
+        image.add_component(DropTarget {
+            accepts: vec!["card".to_string()],
+        });
         e.add_child(image);
     }
 
@@ -407,10 +595,59 @@ pub fn make_testbed(app: &mut App, time_scale: f32) -> Entity {
         }
     }
 
+    {
+        // Both share an atlas page, the sprite is cropped to its first frame within its region
+        let sheet = app.resource.load_image_into_atlas(MARIO_SHEET).unwrap();
+        let mut atlas_sprite = Image::with_region("atlas_sprite", &sheet);
+        atlas_sprite.r_rect = Some(Rect {
+            x: 0.,
+            y: 0.,
+            w: 35.,
+            h: 50.,
+        });
+        atlas_sprite.width = 35.;
+        atlas_sprite.height = 50.;
+        atlas_sprite.x = 100.;
+        atlas_sprite.y = 100.;
+        atlas_sprite.render_type = Some(ImageRenderType::Nearest);
+        atlas_sprite.material = app
+            .resource
+            .load_material(None, OUTLINE_SHADER)
+            .map_err(|err| println!("Failed to load outline material: {}", err))
+            .ok();
+        if let Some(material) = &mut atlas_sprite.material {
+            let texel = 1. / ATLAS_PAGE_SIZE as f32;
+            material
+                .set_uniform(
+                    "outline_color",
+                    MaterialUniform::Value(UniformValue::Vec4([1., 1., 1., 1.])),
+                )
+                .set_uniform(
+                    "texel",
+                    MaterialUniform::Value(UniformValue::Vec2([texel, texel])),
+                );
+        }
+        e.add_component(atlas_sprite);
+
+        let label = app
+            .resource
+            .load_text_into_atlas(&TextLoadInfo {
+                text: "Atlas".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let mut atlas_label = Image::with_region("atlas_label", &label);
+        atlas_label.x = 100.;
+        atlas_label.y = 150.;
+        atlas_label.color.g = 0.;
+        e.add_component(atlas_label);
+    }
+
     {
         let audio_data = app.resource.load_audio_data("res/snd/beep.wav").unwrap();
-        let beep = AudioClip::new("beep", audio_data);
+        let beep = AudioClip::new("beep", "res/snd/beep.wav", audio_data);
         e.add_component(beep);
+        data.beep_timer = Some(app.scheduler.every(e.id(), "beep", BEEP_INTERVAL));
     }
 
     {
@@ -420,31 +657,59 @@ pub fn make_testbed(app: &mut App, time_scale: f32) -> Entity {
     }
 
     {
-        let mut animated_image = make_animated_image(
-            app,
-            "test-animated",
-            DISK_IMAGE_MARIO,
-            35.,
-            50.,
-            Some(10.),
-            Some(crate::core::component::image::ImageRenderType::Nearest),
-        );
-        animated_image.set_x(500.);
-        animated_image.set_y(500.);
-        animated_image.set_scale_x(3.);
-        animated_image.set_scale_y(3.);
-        animated_image.set_width(35.);
-        animated_image.set_height(50.);
-        animated_image.set_rotation(45.);
-        animated_image_add_animation(&mut animated_image, "idle", (0, 0));
-        animated_image_add_animation(&mut animated_image, "walking", (1, 4));
-        animated_image_add_animation(&mut animated_image, "swim", (26, 31));
-        animated_image_set_animation(&mut animated_image, "idle");
+        let animated_image =
+            instantiate_prefab(app, MARIO_PREFAB, json!({ "name": "test-animated" })).unwrap();
 
         e.add_child(animated_image);
     }
 
-    e.add_component(data);
+    {
+        // A second mario rendered offscreen and shown as a picture in picture preview
+        let target = RenderTarget::new(PREVIEW_SIZE, PREVIEW_SIZE, RedrawMode::EveryFrame).unwrap();
+        let mut preview = Image::with_texture(
+            "preview",
+            &target.texture(),
+            PREVIEW_SIZE as f32,
+            PREVIEW_SIZE as f32,
+        );
+        preview.x = app.renderer.viewport.window_size.0 - PREVIEW_SIZE as f32;
+        preview.y = PREVIEW_SIZE as f32;
+        preview.zindex = 5;
+        preview.material = app
+            .resource
+            .load_material(None, WAVE_SHADER)
+            .map_err(|err| println!("Failed to load preview material: {}", err))
+            .ok();
+        if let Some(material) = &mut preview.material {
+            material
+                .set_uniform("time", MaterialUniform::Time(2.))
+                .set_uniform(
+                    "amplitude",
+                    MaterialUniform::Value(UniformValue::Float(0.01)),
+                )
+                .set_uniform("frequency", MaterialUniform::Value(UniformValue::Float(3.)));
+        }
+
+        let mut mario =
+            instantiate_prefab(app, MARIO_PREFAB, json!({ "name": "preview-mario" })).unwrap();
+        mario.set_x(PREVIEW_SIZE as f32 / 2.);
+        mario.set_y(PREVIEW_SIZE as f32 / 2.);
+
+        let mut offscreen = Entity::new("offscreen");
+        offscreen.add_component(target);
+        offscreen.add_child(mario);
+        e.add_child(offscreen);
+        e.add_component(preview);
+    }
+
+    e.set_behavior(data);
 
     e
 }
+
+/// Image alpha of the card, its ClosureBehavior does not handle UpdateRenderable
+fn set_card_alpha(card: &mut Entity, alpha: f32) {
+    if let Ok(image) = card.find_component::<Image>("card") {
+        image.alpha.val = alpha;
+    }
+}
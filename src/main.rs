@@ -6,5 +6,24 @@ mod util;
 use app::App;
 
 fn main() {
-    App::default().run();
+    let mut app = App::default();
+
+    // `--record <path>` saves the session's input, `--replay <path>` plays it back
+    let args: Vec<String> = std::env::args().collect();
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("--record"), Some(path)) => app.record_to(path),
+        (Some("--replay"), Some(path)) => {
+            if let Err(err) = app.replay_from(path) {
+                println!("Failed to load recording {}: {}", path, err);
+                return;
+            }
+        }
+        _ => {}
+    }
+
+    app.run();
+
+    if app.replay_diverged() {
+        std::process::exit(1);
+    }
 }
@@ -1,5 +1,6 @@
 pub mod alpha;
 pub mod color;
+pub mod random;
 pub mod rect;
 pub mod scale;
 pub mod timer;
@@ -0,0 +1,47 @@
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Random numbers for game code. A recording saves the seed so its replay draws the same numbers,
+/// `rand::thread_rng` can not be replayed.
+#[derive(Debug, Clone)]
+pub struct SessionRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SessionRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for SessionRng {
+    fn default() -> Self {
+        SessionRng::new(rand::random())
+    }
+}
+
+impl RngCore for SessionRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}